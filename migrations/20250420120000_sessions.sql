@@ -0,0 +1,29 @@
+-- Sessions
+
+CREATE TABLE sessions (
+    id VARCHAR PRIMARY KEY DEFAULT concat('ses_', gen_random_uuid()),
+    user_id VARCHAR NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_sessions_user_id ON sessions (user_id);
+
+-- Refresh Tokens
+
+CREATE TABLE refresh_tokens (
+    id VARCHAR PRIMARY KEY DEFAULT concat('rtk_', gen_random_uuid()),
+    session_id VARCHAR NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    token_hash VARCHAR NOT NULL UNIQUE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_refresh_tokens_session_id ON refresh_tokens (session_id);
+
+-- Triggers
+
+SELECT trigger_updated_at('sessions');
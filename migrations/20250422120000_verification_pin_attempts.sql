@@ -0,0 +1,5 @@
+ALTER TABLE verification_pins
+    ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN invalidated_at TIMESTAMPTZ;
+
+CREATE INDEX idx_verification_pins_email_created_at ON verification_pins (email, created_at DESC);
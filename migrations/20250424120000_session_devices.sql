@@ -0,0 +1,6 @@
+ALTER TABLE sessions
+    ADD COLUMN user_agent VARCHAR,
+    ADD COLUMN ip_address VARCHAR,
+    ADD COLUMN last_seen_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP;
+
+CREATE INDEX idx_sessions_user_id_last_seen_at ON sessions (user_id, last_seen_at DESC);
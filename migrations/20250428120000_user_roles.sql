@@ -0,0 +1,3 @@
+CREATE TYPE UserRole AS ENUM ('user', 'moderator', 'admin');
+
+ALTER TABLE users ADD COLUMN role UserRole NOT NULL DEFAULT 'user';
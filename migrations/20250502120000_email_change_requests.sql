@@ -0,0 +1,14 @@
+-- Email Change Requests
+
+CREATE TABLE email_change_requests (
+    id VARCHAR PRIMARY KEY DEFAULT concat('ecr_', gen_random_uuid()),
+    user_id VARCHAR NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    new_email VARCHAR NOT NULL,
+    pin VARCHAR NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMPTZ NOT NULL,
+    invalidated_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_email_change_requests_user_id ON email_change_requests (user_id);
@@ -0,0 +1,8 @@
+-- Account Deletion
+
+ALTER TABLE users
+    ADD COLUMN deletion_scheduled_at TIMESTAMPTZ,
+    ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX idx_users_deletion_scheduled_at ON users (deletion_scheduled_at)
+    WHERE deletion_scheduled_at IS NOT NULL;
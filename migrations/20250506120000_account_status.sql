@@ -0,0 +1,6 @@
+CREATE TYPE AccountStatus AS ENUM ('active', 'suspended', 'banned');
+
+ALTER TABLE users
+    ADD COLUMN status AccountStatus NOT NULL DEFAULT 'active',
+    ADD COLUMN suspended_until TIMESTAMPTZ,
+    ADD COLUMN status_reason VARCHAR;
@@ -0,0 +1,12 @@
+-- Follows
+
+CREATE TABLE follows (
+    id VARCHAR PRIMARY KEY DEFAULT concat('flw_', gen_random_uuid()),
+    follower_id VARCHAR NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    followee_id VARCHAR NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (follower_id, followee_id),
+    CHECK (follower_id <> followee_id)
+);
+
+CREATE INDEX idx_follows_followee_id ON follows (followee_id);
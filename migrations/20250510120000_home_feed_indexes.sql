@@ -0,0 +1,4 @@
+-- Home feed reads posts of every followed user newest first
+
+CREATE INDEX idx_posts_user_id_created_at_id ON posts (user_id, created_at DESC, id DESC)
+    WHERE deleted_at IS NULL;
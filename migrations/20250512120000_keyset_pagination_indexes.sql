@@ -0,0 +1,16 @@
+-- Composite indexes backing keyset pagination on (created_at, id)
+
+CREATE INDEX idx_posts_created_at_id ON posts (created_at DESC, id DESC)
+    WHERE deleted_at IS NULL;
+
+CREATE INDEX idx_post_comments_post_id_created_at_id
+    ON post_comments (post_id, created_at DESC, id DESC)
+    WHERE deleted_at IS NULL AND parent_id IS NULL;
+
+CREATE INDEX idx_post_comments_parent_id_created_at_id
+    ON post_comments (parent_id, created_at DESC, id DESC)
+    WHERE deleted_at IS NULL;
+
+CREATE INDEX idx_post_comments_user_id_created_at_id
+    ON post_comments (user_id, created_at DESC, id DESC)
+    WHERE deleted_at IS NULL;
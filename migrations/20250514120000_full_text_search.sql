@@ -0,0 +1,40 @@
+-- Full-text search over posts and comments, the vectors are kept up to date by triggers
+
+ALTER TABLE posts ADD COLUMN search_vector TSVECTOR;
+
+ALTER TABLE post_comments ADD COLUMN search_vector TSVECTOR;
+
+CREATE FUNCTION posts_search_vector_update() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector :=
+        setweight(to_tsvector('english', coalesce(NEW.title, '')), 'A') ||
+        setweight(to_tsvector('english', coalesce(NEW.content, '')), 'B');
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION post_comments_search_vector_update() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.search_vector := to_tsvector('english', coalesce(NEW.content, ''));
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER posts_search_vector_update
+    BEFORE INSERT OR UPDATE OF title, content ON posts
+    FOR EACH ROW EXECUTE FUNCTION posts_search_vector_update();
+
+CREATE TRIGGER post_comments_search_vector_update
+    BEFORE INSERT OR UPDATE OF content ON post_comments
+    FOR EACH ROW EXECUTE FUNCTION post_comments_search_vector_update();
+
+-- Backfill existing rows, the triggers fire on the updated columns
+UPDATE posts SET title = title;
+
+UPDATE post_comments SET content = content;
+
+CREATE INDEX idx_posts_search_vector ON posts USING GIN (search_vector)
+    WHERE deleted_at IS NULL;
+
+CREATE INDEX idx_post_comments_search_vector ON post_comments USING GIN (search_vector)
+    WHERE deleted_at IS NULL;
@@ -0,0 +1,6 @@
+-- Trigram index backing username search, also used for prefix (ILIKE 'abc%') matches
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX idx_users_username_trgm ON users USING GIN (username gin_trgm_ops)
+    WHERE deleted_at IS NULL;
@@ -0,0 +1,45 @@
+-- Hashtags, extracted from the content of posts when they are created or edited
+
+CREATE TABLE tags (
+    id VARCHAR PRIMARY KEY DEFAULT concat('tag_', gen_random_uuid()),
+    name VARCHAR(50) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE post_tags (
+    post_id VARCHAR NOT NULL REFERENCES posts(id) ON DELETE CASCADE,
+    tag_id VARCHAR NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (post_id, tag_id)
+);
+
+CREATE INDEX idx_post_tags_tag_id ON post_tags (tag_id);
+
+-- Backfill existing posts, following the rules of `extract_hashtags`: the length is
+-- checked once lowercased and only the first 30 distinct hashtags of a post are kept
+CREATE TEMPORARY TABLE backfilled_hashtags AS
+SELECT post_id, name
+FROM (
+    SELECT post_id, name,
+        ROW_NUMBER() OVER (PARTITION BY post_id ORDER BY first_position) AS position
+    FROM (
+        SELECT p.id AS post_id, lower(m.match[1]) AS name, MIN(m.position) AS first_position
+        FROM posts p,
+        regexp_matches(p.content, '(?:^|[^[:alnum:]_])#([[:alnum:]_]+)', 'g')
+            WITH ORDINALITY AS m(match, position)
+        GROUP BY p.id, lower(m.match[1])
+    ) matches
+    WHERE length(name) <= 50 AND name !~ '^[0-9]+$'
+) hashtags
+WHERE position <= 30;
+
+INSERT INTO tags (name)
+SELECT DISTINCT name FROM backfilled_hashtags
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO post_tags (post_id, tag_id)
+SELECT h.post_id, t.id
+FROM backfilled_hashtags h
+JOIN tags t ON t.name = h.name;
+
+DROP TABLE backfilled_hashtags;
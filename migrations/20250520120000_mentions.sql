@@ -0,0 +1,34 @@
+-- Mentions of users in posts and comments, resolved to user ids when the content is saved
+-- so they keep pointing to the same account after a rename. Offsets are in bytes.
+
+CREATE TABLE mentions (
+    id VARCHAR PRIMARY KEY DEFAULT concat('mnt_', gen_random_uuid()),
+    user_id VARCHAR NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    post_id VARCHAR REFERENCES posts(id) ON DELETE CASCADE,
+    comment_id VARCHAR REFERENCES post_comments(id) ON DELETE CASCADE,
+    start_offset INTEGER NOT NULL,
+    end_offset INTEGER NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    CHECK ((post_id IS NULL) <> (comment_id IS NULL))
+);
+
+CREATE INDEX idx_mentions_post_id ON mentions (post_id) WHERE post_id IS NOT NULL;
+CREATE INDEX idx_mentions_comment_id ON mentions (comment_id) WHERE comment_id IS NOT NULL;
+CREATE INDEX idx_mentions_user_id ON mentions (user_id);
+
+-- Notifications
+
+CREATE TYPE NotificationKind AS ENUM ('mention');
+
+CREATE TABLE notifications (
+    id VARCHAR PRIMARY KEY DEFAULT concat('ntf_', gen_random_uuid()),
+    user_id VARCHAR NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind NotificationKind NOT NULL,
+    actor_id VARCHAR NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    post_id VARCHAR REFERENCES posts(id) ON DELETE CASCADE,
+    comment_id VARCHAR REFERENCES post_comments(id) ON DELETE CASCADE,
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_notifications_user_id_created_at ON notifications (user_id, created_at DESC);
@@ -0,0 +1,6 @@
+-- The initial index made post_id unique, so a post could only ever be liked by a single
+-- user. Likes are unique per user instead.
+
+DROP INDEX idx_post_likes_post_id;
+
+CREATE UNIQUE INDEX idx_post_likes_post_id_user_id ON post_likes (post_id, user_id);
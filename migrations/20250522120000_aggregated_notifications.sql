@@ -0,0 +1,42 @@
+-- Aggregated notifications: events of the same kind on the same target are grouped into
+-- one unread notification listing every actor ("alice and 12 others liked your post")
+
+ALTER TYPE NotificationKind ADD VALUE 'like';
+ALTER TYPE NotificationKind ADD VALUE 'reply';
+ALTER TYPE NotificationKind ADD VALUE 'follow';
+
+CREATE TABLE notification_actors (
+    notification_id VARCHAR NOT NULL REFERENCES notifications(id) ON DELETE CASCADE,
+    actor_id VARCHAR NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (notification_id, actor_id)
+);
+
+INSERT INTO notification_actors (notification_id, actor_id, created_at)
+SELECT id, actor_id, created_at FROM notifications;
+
+ALTER TABLE notifications DROP COLUMN actor_id;
+
+-- Bumped every time an actor is added, notifications are listed by latest activity
+ALTER TABLE notifications ADD COLUMN updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP;
+
+UPDATE notifications SET updated_at = created_at;
+
+DROP INDEX idx_notifications_user_id_created_at;
+
+CREATE INDEX idx_notifications_user_id_updated_at ON notifications (user_id, updated_at DESC, id DESC);
+
+-- A mention removed and added back by edits could have been notified twice
+DELETE FROM notifications n
+USING notifications newer
+WHERE n.read_at IS NULL AND newer.read_at IS NULL
+    AND n.user_id = newer.user_id
+    AND n.kind = newer.kind
+    AND n.post_id IS NOT DISTINCT FROM newer.post_id
+    AND n.comment_id IS NOT DISTINCT FROM newer.comment_id
+    AND (n.created_at, n.id) < (newer.created_at, newer.id);
+
+-- At most one unread notification per recipient, kind and target, new events join it
+CREATE UNIQUE INDEX idx_notifications_unread_group ON notifications (
+    user_id, kind, COALESCE(post_id, ''), COALESCE(comment_id, '')
+) WHERE read_at IS NULL;
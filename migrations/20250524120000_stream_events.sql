@@ -0,0 +1,54 @@
+-- Real-time events, published on commit to the `stream_events` channel. Every server
+-- instance LISTENs to it and relays the events to its connected clients.
+-- Payloads only carry ids and counters to stay well below the 8000 bytes NOTIFY limit.
+
+CREATE FUNCTION notify_notification_stream_event() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('stream_events', json_build_object(
+        'type', 'notification',
+        'userId', NEW.user_id,
+        'notificationId', NEW.id,
+        'kind', NEW.kind
+    )::TEXT);
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+-- Also fires when a new actor joins an aggregated notification
+CREATE TRIGGER notifications_stream_event
+    AFTER INSERT OR UPDATE OF updated_at ON notifications
+    FOR EACH ROW EXECUTE FUNCTION notify_notification_stream_event();
+
+CREATE FUNCTION notify_comment_stream_event() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('stream_events', json_build_object(
+        'type', 'comment',
+        'postId', NEW.post_id,
+        'commentId', NEW.id,
+        'parentId', NEW.parent_id,
+        'userId', NEW.user_id
+    )::TEXT);
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER post_comments_stream_event
+    AFTER INSERT ON post_comments
+    FOR EACH ROW EXECUTE FUNCTION notify_comment_stream_event();
+
+CREATE FUNCTION notify_likes_stream_event() RETURNS TRIGGER AS $$
+DECLARE
+    liked_post_id VARCHAR := COALESCE(NEW.post_id, OLD.post_id);
+BEGIN
+    PERFORM pg_notify('stream_events', json_build_object(
+        'type', 'likes',
+        'postId', liked_post_id,
+        'likesCount', (SELECT COUNT(*) FROM post_likes WHERE post_id = liked_post_id)
+    )::TEXT);
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER post_likes_stream_event
+    AFTER INSERT OR DELETE ON post_likes
+    FOR EACH ROW EXECUTE FUNCTION notify_likes_stream_event();
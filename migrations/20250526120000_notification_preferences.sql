@@ -0,0 +1,26 @@
+-- Per event type delivery of notifications and activity digest emails
+
+CREATE TYPE NotificationDelivery AS ENUM ('inapp', 'email', 'none');
+
+CREATE TYPE DigestFrequency AS ENUM ('none', 'daily', 'weekly');
+
+-- Kinds without a row are delivered in-app
+CREATE TABLE notification_preferences (
+    user_id VARCHAR NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    kind NotificationKind NOT NULL,
+    delivery NotificationDelivery NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, kind)
+);
+
+CREATE TABLE digest_subscriptions (
+    user_id VARCHAR PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    frequency DigestFrequency NOT NULL,
+    -- Start of the period covered by the next digest
+    last_sent_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE notifications ADD COLUMN emailed_at TIMESTAMPTZ;
+
+CREATE INDEX idx_notifications_pending_email ON notifications (updated_at)
+    WHERE emailed_at IS NULL AND read_at IS NULL;
@@ -0,0 +1,105 @@
+-- One-to-one and small group conversations between users
+
+CREATE TABLE conversations (
+    id VARCHAR PRIMARY KEY DEFAULT concat('cnv_', gen_random_uuid()),
+    -- Only group conversations have a title
+    title VARCHAR(100),
+    is_group BOOLEAN NOT NULL DEFAULT FALSE,
+    -- Sorted ids of both users of a one-to-one conversation, so a pair only has one
+    direct_key VARCHAR UNIQUE,
+    created_by VARCHAR REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    -- Conversations are listed by their latest message
+    last_message_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE conversation_participants (
+    conversation_id VARCHAR NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    user_id VARCHAR NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Read receipt, the participant read every message sent up to then
+    last_read_at TIMESTAMPTZ,
+    joined_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (conversation_id, user_id)
+);
+
+CREATE INDEX idx_conversation_participants_user_id ON conversation_participants (user_id);
+
+CREATE TABLE messages (
+    id VARCHAR PRIMARY KEY DEFAULT concat('msg_', gen_random_uuid()),
+    conversation_id VARCHAR NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+    user_id VARCHAR NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    content VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ,
+    deleted_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_messages_conversation_id_created_at_id
+    ON messages (conversation_id, created_at DESC, id DESC);
+
+SELECT trigger_updated_at('messages');
+
+-- Files uploaded through /upload and sent along a message
+CREATE TABLE message_attachments (
+    id VARCHAR PRIMARY KEY DEFAULT concat('mat_', gen_random_uuid()),
+    message_id VARCHAR NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    media_url VARCHAR NOT NULL,
+    media_type MediaType NOT NULL,
+    mime_type VARCHAR(100) NOT NULL,
+    width INTEGER,
+    height INTEGER,
+    file_size INTEGER,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_message_attachments_message_id ON message_attachments (message_id);
+
+-- Real-time delivery to the participants, see 20250524120000_stream_events.sql.
+-- Groups are small enough for their participant ids to fit in the payload.
+
+CREATE FUNCTION notify_message_stream_event() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('stream_events', json_build_object(
+        'type', 'message',
+        'action', CASE
+            WHEN TG_OP = 'INSERT' THEN 'created'
+            WHEN NEW.deleted_at IS NOT NULL THEN 'deleted'
+            ELSE 'updated'
+        END,
+        'conversationId', NEW.conversation_id,
+        'messageId', NEW.id,
+        'userId', NEW.user_id,
+        'participantIds', (
+            SELECT json_agg(user_id) FROM conversation_participants
+            WHERE conversation_id = NEW.conversation_id
+        )
+    )::TEXT);
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER messages_stream_event
+    AFTER INSERT OR UPDATE OF content, deleted_at ON messages
+    FOR EACH ROW EXECUTE FUNCTION notify_message_stream_event();
+
+CREATE FUNCTION notify_read_receipt_stream_event() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('stream_events', json_build_object(
+        'type', 'readReceipt',
+        'conversationId', NEW.conversation_id,
+        'userId', NEW.user_id,
+        'readAt', NEW.last_read_at,
+        'participantIds', (
+            SELECT json_agg(user_id) FROM conversation_participants
+            WHERE conversation_id = NEW.conversation_id
+        )
+    )::TEXT);
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER conversation_participants_stream_event
+    AFTER UPDATE OF last_read_at ON conversation_participants
+    FOR EACH ROW
+    WHEN (NEW.last_read_at IS DISTINCT FROM OLD.last_read_at)
+    EXECUTE FUNCTION notify_read_receipt_stream_event();
@@ -0,0 +1,12 @@
+-- Verification attempts are limited per email over a window instead of per pin, so
+-- requesting a new pin doesn't hand out a fresh set of attempts
+
+CREATE TABLE verification_attempts (
+    id VARCHAR PRIMARY KEY DEFAULT concat('vat_', gen_random_uuid()),
+    email VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_verification_attempts_email_created_at ON verification_attempts (email, created_at);
+
+ALTER TABLE verification_pins DROP COLUMN attempts;
@@ -0,0 +1,31 @@
+-- Files uploaded through /upload. Posts and messages only accept files uploaded by
+-- their author, and purging an account only removes files it owns.
+-- Anonymous uploads, such as the profile image sent along a sign up, are claimed by
+-- the account that uses them.
+
+CREATE TABLE uploads (
+    id VARCHAR PRIMARY KEY DEFAULT concat('upl_', gen_random_uuid()),
+    url VARCHAR NOT NULL UNIQUE,
+    user_id VARCHAR REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_uploads_user_id ON uploads (user_id);
+
+-- Files uploaded before are owned by whoever used them first
+INSERT INTO uploads (url, user_id, created_at)
+SELECT DISTINCT ON (url) url, user_id, created_at
+FROM (
+    SELECT pm.media_url AS url, p.user_id, pm.created_at
+    FROM posts_media pm
+    JOIN posts p ON p.id = pm.post_id
+    UNION ALL
+    SELECT ma.media_url, m.user_id, ma.created_at
+    FROM message_attachments ma
+    JOIN messages m ON m.id = ma.message_id
+    UNION ALL
+    SELECT profile_image_url, id, created_at
+    FROM users
+    WHERE profile_image_url IS NOT NULL
+) used
+ORDER BY url, created_at;
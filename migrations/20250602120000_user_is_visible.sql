@@ -0,0 +1,7 @@
+-- Whether the content of a user is shown to others. Suspended and banned users are
+-- hidden until the restriction is lifted, suspensions lift themselves once
+-- suspended_until passes. A plain SQL function so the planner inlines it.
+
+CREATE FUNCTION user_is_visible(u users) RETURNS BOOLEAN AS $$
+    SELECT u.status = 'active' OR (u.status = 'suspended' AND u.suspended_until <= NOW())
+$$ LANGUAGE sql STABLE;
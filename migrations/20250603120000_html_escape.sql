@@ -0,0 +1,11 @@
+-- Escapes text to be embedded in HTML. Search snippets escape the content before
+-- ts_headline wraps the matches in <mark> tags, so only those tags are markup.
+
+CREATE FUNCTION html_escape(content TEXT) RETURNS TEXT AS $$
+    SELECT replace(replace(replace(replace(replace(content,
+        '&', '&amp;'),
+        '<', '&lt;'),
+        '>', '&gt;'),
+        '"', '&quot;'),
+        '''', '&#39;')
+$$ LANGUAGE sql IMMUTABLE;
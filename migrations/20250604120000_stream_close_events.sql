@@ -0,0 +1,40 @@
+-- Events that close the real-time streams opened with a session, see
+-- 20250524120000_stream_events.sql. They are never delivered to clients.
+
+CREATE FUNCTION notify_session_revoked_stream_event() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('stream_events', json_build_object(
+        'type', 'sessionRevoked',
+        'sessionId', OLD.id
+    )::TEXT);
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER sessions_revoked_stream_event
+    AFTER UPDATE OF revoked_at ON sessions
+    FOR EACH ROW
+    WHEN (OLD.revoked_at IS NULL AND NEW.revoked_at IS NOT NULL)
+    EXECUTE FUNCTION notify_session_revoked_stream_event();
+
+-- Purged accounts lose their sessions
+CREATE TRIGGER sessions_deleted_stream_event
+    AFTER DELETE ON sessions
+    FOR EACH ROW EXECUTE FUNCTION notify_session_revoked_stream_event();
+
+CREATE FUNCTION notify_account_restricted_stream_event() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('stream_events', json_build_object(
+        'type', 'accountRestricted',
+        'userId', NEW.id
+    )::TEXT);
+    RETURN NULL;
+END
+$$ LANGUAGE plpgsql;
+
+-- A suspension or ban
+CREATE TRIGGER users_restricted_stream_event
+    AFTER UPDATE OF status, suspended_until ON users
+    FOR EACH ROW
+    WHEN (user_is_visible(OLD) AND NOT user_is_visible(NEW))
+    EXECUTE FUNCTION notify_account_restricted_stream_event();
@@ -0,0 +1,20 @@
+-- Only notifications of a kind the user gets by email wait for the mail job. Indexing
+-- every unread notification made the pending index, and the scan of the mail job,
+-- grow with the in-app notifications of every user.
+
+ALTER TABLE notifications ADD COLUMN email_pending BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE notifications n
+SET email_pending = TRUE
+FROM notification_preferences np
+WHERE np.user_id = n.user_id
+    AND np.kind = n.kind
+    AND np.delivery = 'email'
+    AND n.emailed_at IS NULL
+    AND n.read_at IS NULL
+    AND n.created_at >= np.updated_at;
+
+DROP INDEX idx_notifications_pending_email;
+
+CREATE INDEX idx_notifications_pending_email ON notifications (updated_at)
+    WHERE email_pending AND read_at IS NULL;
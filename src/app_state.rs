@@ -1,13 +1,21 @@
 use sqlx::PgPool;
 use std::sync::Arc;
 
+use crate::core::services::{event_hub::EventHub, jwt_keys::JwtKeyStore};
+
 pub struct AppState {
     pub db: PgPool,
+    pub jwt_keys: Arc<JwtKeyStore>,
+    pub events: EventHub,
 }
 
 impl AppState {
-    pub fn new(db: PgPool) -> Self {
-        Self { db }
+    pub fn new(db: PgPool, jwt_keys: Arc<JwtKeyStore>, events: EventHub) -> Self {
+        Self {
+            db,
+            jwt_keys,
+            events,
+        }
     }
 }
 
@@ -1,4 +1,4 @@
-use std::{str::FromStr, sync::LazyLock};
+use std::{net::IpAddr, str::FromStr, sync::LazyLock};
 
 use std::time::Duration;
 
@@ -31,9 +31,14 @@ pub struct Config {
     pub env: Env,
     pub db_url: String,
     pub mail_config: MailConfig,
-    pub jwt_secret: String,
+    pub jwt_keys_dir: String,
+    pub jwt_key_rotation_interval: Duration,
     pub jwt_expiration_duration: Duration,
+    pub refresh_token_expiration_duration: Duration,
+    pub account_deletion_grace_period: Duration,
+    pub unsubscribe_secret: String,
     pub request_body_limit: usize,
+    pub trusted_proxies: Vec<IpAddr>,
     pub port: u16,
 }
 
@@ -45,16 +50,41 @@ impl Config {
 
         let mail_config = MailConfig::new();
 
-        let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET is not set");
+        let jwt_keys_dir = std::env::var("JWT_KEYS_DIR").unwrap_or("keys".to_string());
+
+        let jwt_key_rotation_interval = std::env::var("JWT_KEY_ROTATION_INTERVAL")
+            .map(|s| Duration::from_secs(s.parse::<u64>().unwrap()))
+            .unwrap_or(Duration::from_secs(60 * 60 * 24 * 30)); // 30 days
 
         let jwt_expiration_duration = std::env::var("JWT_EXPIRATION_DURATION")
             .map(|s| Duration::from_secs(s.parse::<u64>().unwrap()))
             .expect("JWT_EXPIRATION_DURATION is not set");
 
+        let refresh_token_expiration_duration = std::env::var("REFRESH_TOKEN_EXPIRATION_DURATION")
+            .map(|s| Duration::from_secs(s.parse::<u64>().unwrap()))
+            .unwrap_or(Duration::from_secs(60 * 60 * 24 * 30)); // 30 days
+
+        let account_deletion_grace_period = std::env::var("ACCOUNT_DELETION_GRACE_PERIOD")
+            .map(|s| Duration::from_secs(s.parse::<u64>().unwrap()))
+            .unwrap_or(Duration::from_secs(60 * 60 * 24 * 30)); // 30 days
+
+        let unsubscribe_secret =
+            std::env::var("UNSUBSCRIBE_SECRET").expect("UNSUBSCRIBE_SECRET is not set");
+
         let request_body_limit = std::env::var("REQUEST_BODY_LIMIT")
             .map(|s| s.parse::<u64>().unwrap())
             .unwrap_or(5 * 1024 * 1024); // 5 Mb
 
+        let trusted_proxies = std::env::var("TRUSTED_PROXIES")
+            .map(|s| {
+                s.split(',')
+                    .map(str::trim)
+                    .filter(|ip| !ip.is_empty())
+                    .map(|ip| ip.parse::<IpAddr>().unwrap())
+                    .collect()
+            })
+            .unwrap_or_default();
+
         let port = std::env::var("PORT")
             .map(|s| s.parse::<u16>().unwrap())
             .unwrap_or(3001);
@@ -63,14 +93,20 @@ impl Config {
             env,
             db_url,
             mail_config,
-            jwt_secret,
+            jwt_keys_dir,
+            jwt_key_rotation_interval,
             jwt_expiration_duration,
+            refresh_token_expiration_duration,
+            account_deletion_grace_period,
+            unsubscribe_secret,
             request_body_limit: request_body_limit as usize,
+            trusted_proxies,
             port,
         }
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
 pub enum Env {
     DEV,
     RELEASE,
@@ -4,20 +4,91 @@ use crate::config::CONFIG;
 
 pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
 
-pub const WEBSITE_URL: LazyLock<String> =
+pub static WEBSITE_URL: LazyLock<String> =
     LazyLock::new(|| std::env::var("WEBSITE_URL").unwrap_or("https://example.com".to_string()));
 
 pub const PIN_RANGE: Range<u32> = 1000000..9999999;
 
 pub const VERIFICATION_PIN_EXPIRATION_TIME: Duration = Duration::from_secs(60 * 5); // 5 minutes
 
+pub const VERIFICATION_PIN_RESEND_COOLDOWN: Duration = Duration::from_secs(60); // 1 minute
+
+// Login pins are limited per email within the window, whatever the number of pins
+// requested, email change pins per request
+pub const MAX_VERIFICATION_PIN_ATTEMPTS: i32 = 5;
+
+pub const VERIFICATION_PIN_ATTEMPTS_WINDOW: Duration = Duration::from_secs(60 * 60); // 1 hour
+
+pub const API_KEY_PREFIX: &str = "rsl_";
+
+pub const TOTP_ISSUER: &str = "Rustle";
+
+pub const TOTP_STEP: Duration = Duration::from_secs(30);
+
+pub const TOTP_DIGITS: u32 = 6;
+
+// Codes from the previous and next step are accepted to tolerate clock drift
+pub const TOTP_ALLOWED_DRIFT_STEPS: i64 = 1;
+
+pub const RECOVERY_CODES_COUNT: usize = 10;
+
+pub const TWO_FACTOR_CHALLENGE_EXPIRATION_TIME: Duration = Duration::from_secs(60 * 5); // 5 minutes
+
+pub const MAX_TWO_FACTOR_ATTEMPTS: i32 = 5;
+
+pub const ACCOUNT_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60); // 1 hour
+
 pub const DEFAULT_POSTS_PAGINATION_LIMIT: i32 = 20;
 
-pub const SERVER_URL: LazyLock<String> = LazyLock::new(|| {
+pub const MAX_PAGINATION_LIMIT: i32 = 100;
+
+pub const DEFAULT_USER_SEARCH_LIMIT: i64 = 10;
+
+pub const MAX_USER_SEARCH_LIMIT: i64 = 25;
+
+pub const MAX_HASHTAG_LENGTH: usize = 50;
+
+pub const MAX_HASHTAGS_PER_POST: usize = 30;
+
+pub const MAX_MENTIONS_PER_CONTENT: usize = 20;
+
+// How many of the latest actors are listed with each notification
+pub const NOTIFICATION_ACTORS_PREVIEW: i64 = 3;
+
+// Postgres channel the stream events are published to, see the stream_events migration
+pub const STREAM_EVENTS_CHANNEL: &str = "stream_events";
+
+// Events buffered for each stream client, slower clients skip events and are told so
+pub const STREAM_EVENTS_CAPACITY: usize = 1024;
+
+pub const STREAM_LISTENER_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+pub const MAX_STREAM_WATCHED_POSTS: usize = 100;
+
+// Including the user starting the conversation
+pub const MAX_CONVERSATION_PARTICIPANTS: usize = 10;
+
+pub const MAX_MESSAGE_ATTACHMENTS: usize = 10;
+
+pub const NOTIFICATION_MAIL_INTERVAL: Duration = Duration::from_secs(60); // 1 minute
+
+// Notifications are emailed once they stopped changing for this long, so an email covers
+// as many grouped events as possible
+pub const NOTIFICATION_MAIL_DELAY: Duration = Duration::from_secs(60 * 5); // 5 minutes
+
+// Trending tags are ranked by how many posts used them within this window
+pub const TRENDING_TAGS_WINDOW: Duration = Duration::from_secs(60 * 60 * 24); // 24 hours
+
+pub const TRENDING_TAGS_LIMIT: i64 = 10;
+
+// Search relevance is divided by 2 for every this much age of a post or comment
+pub const SEARCH_RECENCY_HALF_LIFE: Duration = Duration::from_secs(60 * 60 * 24 * 30); // 30 days
+
+pub static SERVER_URL: LazyLock<String> = LazyLock::new(|| {
     std::env::var("SERVER_URL").unwrap_or(format!("http://localhost:{}", CONFIG.port).to_string())
 });
 
-pub const DISK_STORAGE_PATH: LazyLock<String> =
+pub static DISK_STORAGE_PATH: LazyLock<String> =
     LazyLock::new(|| std::env::var("DISK_STORAGE_PATH").unwrap_or("uploads".to_string()));
 
 // pub const TEST_PIN: u32 = 1234567;
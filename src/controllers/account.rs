@@ -0,0 +1,141 @@
+use axum::{
+    Extension,
+    extract::State,
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use chrono::Utc;
+use serde_json::json;
+
+use crate::{
+    app_state::SharedAppState,
+    config::CONFIG,
+    core::{
+        error::http_error::HttpError, extractors::json::Json, layers::auth_layer::AuthUser,
+        utils::archive,
+    },
+    extensions::StorageServiceExt,
+    service::{self, account::AccountExport},
+};
+
+/// Schedules the account for deletion. It can be restored until the grace period
+/// passes, after which it is purged in the background.
+pub async fn delete_account(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, HttpError> {
+    let user = service::account::schedule_account_deletion(
+        &app_state.db,
+        &user_id,
+        Utc::now() + CONFIG.account_deletion_grace_period,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "success": true,
+            "message": "Account scheduled for deletion",
+            "deletionScheduledAt": user.deletion_scheduled_at,
+        })),
+    ))
+}
+
+pub async fn restore_account(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, HttpError> {
+    let user = service::account::cancel_account_deletion(&app_state.db, &user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    match user {
+        Some(user) => Ok(Json(user)),
+        None => Err(HttpError::bad_request(
+            "Account is not scheduled for deletion".into(),
+        )),
+    }
+}
+
+/// Returns a zip archive with everything stored about the user, including the
+/// files they uploaded.
+pub async fn export_account(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Extension(storage_provider): StorageServiceExt,
+) -> Result<impl IntoResponse, HttpError> {
+    let export = match service::account::get_account_export(&app_state.db, &user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+    {
+        Some(export) => export,
+        None => return Err(HttpError::not_found("User not found".into())),
+    };
+
+    let mut entries =
+        to_json_entries(&export).map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let media = export
+        .posts
+        .iter()
+        .flat_map(|post| post.media.iter())
+        .map(|media| (media.id.clone(), media.media_url.clone()))
+        .chain(
+            export
+                .user
+                .profile_image_url
+                .iter()
+                .map(|url| ("profile".to_string(), url.clone())),
+        );
+
+    for (id, url) in media {
+        match storage_provider.storage.download_file(&url).await {
+            Ok(data) => entries.push((media_entry_path(&id, &url), data.to_vec())),
+            Err(e) => tracing::warn!("Skipping {} in export of {}: {}", url, user_id, e),
+        }
+    }
+
+    let archive = tokio::task::spawn_blocking(move || archive::build_zip(entries))
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"rustle-export-{}.zip\"", user_id),
+            ),
+        ],
+        archive,
+    ))
+}
+
+fn to_json_entries(export: &AccountExport) -> serde_json::Result<Vec<(String, Vec<u8>)>> {
+    Ok(vec![
+        (
+            "profile.json".to_string(),
+            serde_json::to_vec_pretty(&export.user)?,
+        ),
+        (
+            "posts.json".to_string(),
+            serde_json::to_vec_pretty(&export.posts)?,
+        ),
+        (
+            "comments.json".to_string(),
+            serde_json::to_vec_pretty(&export.comments)?,
+        ),
+        (
+            "likes.json".to_string(),
+            serde_json::to_vec_pretty(&export.likes)?,
+        ),
+    ])
+}
+
+fn media_entry_path(id: &str, url: &str) -> String {
+    let file_name = url.rsplit('/').next().unwrap_or_default();
+
+    format!("media/{}_{}", id, file_name)
+}
@@ -0,0 +1,233 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+};
+use chrono::Utc;
+use validator::Validate;
+
+use crate::{
+    app_state::SharedAppState,
+    core::{
+        error::http_error::HttpError,
+        extractors::{
+            current_user::{Admin, Moderator, RequireRole},
+            json::Json,
+        },
+    },
+    dtos::admin::{UpdateAccountStatusDto, UpdateUserRoleDto},
+    models::{AccountState, AccountStatus, UserRole},
+    service,
+    types::PaginationQuery,
+};
+
+pub async fn get_users(
+    _: RequireRole<Moderator>,
+    State(app_state): State<SharedAppState>,
+    Query(query): Query<PaginationQuery>,
+) -> Result<impl IntoResponse, HttpError> {
+    let users = service::user::get_users(&app_state.db, query.offset, query.limit)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(users))
+}
+
+pub async fn update_user_role(
+    RequireRole(admin, _): RequireRole<Admin>,
+    State(app_state): State<SharedAppState>,
+    Path(user_id): Path<String>,
+    Json(body): Json<UpdateUserRoleDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    if admin.id == user_id {
+        return Err(HttpError::bad_request(
+            "You can't change your own role".to_string(),
+        ));
+    }
+
+    let user = service::user::update_user_role(&app_state.db, &user_id, body.role)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    match user {
+        Some(user) => Ok(Json(user)),
+        None => Err(HttpError::not_found("User not found".into())),
+    }
+}
+
+/// Suspends, bans or reinstates a user. Moderators can only act on users with a
+/// lower role than their own, and only admins can ban or lift a ban.
+pub async fn update_account_status(
+    RequireRole(moderator, _): RequireRole<Moderator>,
+    State(app_state): State<SharedAppState>,
+    Path(user_id): Path<String>,
+    Json(body): Json<UpdateAccountStatusDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation_error)?;
+
+    if moderator.id == user_id {
+        return Err(HttpError::bad_request(
+            "You can't change your own account status".to_string(),
+        ));
+    }
+
+    let user = match service::user::get_user_by_id(&app_state.db, &user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+    {
+        Some(user) => user,
+        None => return Err(HttpError::not_found("User not found".into())),
+    };
+
+    if user.role >= moderator.role {
+        return Err(HttpError::forbidden(
+            "You can't change the account status of this user".to_string(),
+        ));
+    }
+
+    check_ban_permission(moderator.role, user.account_state.status, body.status)?;
+
+    let account_state = match body.status {
+        AccountStatus::Active => AccountState {
+            status: AccountStatus::Active,
+            suspended_until: None,
+            status_reason: None,
+        },
+        AccountStatus::Suspended => match body.suspended_until {
+            Some(until) if until > Utc::now() => AccountState {
+                status: AccountStatus::Suspended,
+                suspended_until: Some(until),
+                status_reason: body.reason,
+            },
+            _ => {
+                return Err(HttpError::bad_request(
+                    "Suspensions require a suspendedUntil date in the future".to_string(),
+                ));
+            }
+        },
+        AccountStatus::Banned => AccountState {
+            status: AccountStatus::Banned,
+            suspended_until: None,
+            status_reason: body.reason,
+        },
+    };
+
+    let user = service::user::update_account_state(&app_state.db, &user_id, &account_state)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    match user {
+        Some(user) => Ok(Json(user)),
+        None => Err(HttpError::not_found("User not found".into())),
+    }
+}
+
+/// Bans are admin-only both ways, moderators can neither ban a user nor change the
+/// status of a banned one.
+fn check_ban_permission(
+    role: UserRole,
+    current: AccountStatus,
+    requested: AccountStatus,
+) -> Result<(), HttpError> {
+    if role >= UserRole::Admin {
+        return Ok(());
+    }
+
+    if requested == AccountStatus::Banned {
+        return Err(HttpError::forbidden(
+            "Only admins can ban users".to_string(),
+        ));
+    }
+
+    if current == AccountStatus::Banned {
+        return Err(HttpError::forbidden(
+            "Only admins can change the account status of a banned user".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moderators_cannot_ban() {
+        assert!(
+            check_ban_permission(
+                UserRole::Moderator,
+                AccountStatus::Active,
+                AccountStatus::Banned
+            )
+            .is_err()
+        );
+        assert!(
+            check_ban_permission(
+                UserRole::Moderator,
+                AccountStatus::Suspended,
+                AccountStatus::Banned
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn moderators_cannot_lift_a_ban() {
+        assert!(
+            check_ban_permission(
+                UserRole::Moderator,
+                AccountStatus::Banned,
+                AccountStatus::Active
+            )
+            .is_err()
+        );
+        assert!(
+            check_ban_permission(
+                UserRole::Moderator,
+                AccountStatus::Banned,
+                AccountStatus::Suspended
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn moderators_can_suspend_and_reinstate() {
+        assert!(
+            check_ban_permission(
+                UserRole::Moderator,
+                AccountStatus::Active,
+                AccountStatus::Suspended
+            )
+            .is_ok()
+        );
+        assert!(
+            check_ban_permission(
+                UserRole::Moderator,
+                AccountStatus::Suspended,
+                AccountStatus::Active
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn admins_can_ban_and_lift_a_ban() {
+        assert!(
+            check_ban_permission(
+                UserRole::Admin,
+                AccountStatus::Active,
+                AccountStatus::Banned
+            )
+            .is_ok()
+        );
+        assert!(
+            check_ban_permission(
+                UserRole::Admin,
+                AccountStatus::Banned,
+                AccountStatus::Active
+            )
+            .is_ok()
+        );
+    }
+}
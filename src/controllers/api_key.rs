@@ -0,0 +1,90 @@
+use axum::{
+    Extension,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::Utc;
+use serde_json::json;
+use validator::Validate;
+
+use crate::{
+    app_state::SharedAppState,
+    constants::API_KEY_PREFIX,
+    core::{
+        error::http_error::HttpError, extractors::json::Json, layers::auth_layer::AuthUser,
+        utils::token,
+    },
+    dtos::api_key::{CreateApiKeyDto, CreateApiKeyResponseDto},
+    service,
+};
+
+// Enough of the token to tell keys apart in listings without revealing it
+const TOKEN_PREFIX_LENGTH: usize = 12;
+
+pub async fn create_api_key(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Json(body): Json<CreateApiKeyDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation_error)?;
+
+    if let Some(expires_at) = body.expires_at
+        && expires_at <= Utc::now()
+    {
+        return Err(HttpError::bad_request(
+            "Expiration date must be in the future".to_string(),
+        ));
+    }
+
+    let token = format!("{}{}", API_KEY_PREFIX, token::generate_opaque_token());
+
+    let api_key = service::api_key::create_api_key(
+        &app_state.db,
+        &user_id,
+        &body.name,
+        &token[..TOKEN_PREFIX_LENGTH],
+        &token::hash_token(&token),
+        &body.scopes,
+        body.expires_at,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateApiKeyResponseDto { api_key, token }),
+    ))
+}
+
+pub async fn get_api_keys(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, HttpError> {
+    let api_keys = service::api_key::get_user_api_keys(&app_state.db, &user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(api_keys))
+}
+
+pub async fn revoke_api_key(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Path(api_key_id): Path<String>,
+) -> Result<impl IntoResponse, HttpError> {
+    let api_key = service::api_key::revoke_api_key(&app_state.db, &user_id, &api_key_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    match api_key {
+        Some(_) => Ok((
+            StatusCode::ACCEPTED,
+            Json(json!({
+                "success": true,
+                "message": "Api key revoked successfully"
+            })),
+        )),
+        None => Err(HttpError::not_found("Api key not found".into())),
+    }
+}
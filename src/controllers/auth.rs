@@ -1,29 +1,66 @@
 use axum::extract::{Extension, State};
+use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use chrono::Utc;
+use serde_json::json;
 use validator::Validate;
 
 use crate::core::error::http_error::HttpError;
-use crate::core::extractors::json::Json;
-use crate::dtos::auth::VerifyEmailDto;
+use crate::core::extractors::{client_info::ClientInfo, json::Json};
+use crate::core::layers::auth_layer::{AuthSession, AuthUser};
+use crate::dtos::auth::{
+    RefreshTokenDto, RefreshTokenResponseDto, TwoFactorChallengeResponseDto, VerifyEmailDto,
+    VerifyTwoFactorDto,
+};
 use crate::extensions::MailServiceExt;
-use crate::service::user::{create_user_if_not_exists, get_user_by_email};
-use crate::service::verification_pin::{create_verification_pin, get_verification_pin};
+use crate::models::User;
+use crate::service::session::{self, RefreshTokenRotation};
+use crate::service::two_factor;
+use crate::service::user::{
+    create_user_if_not_exists, get_user_by_email, get_user_by_id, mark_user_verified,
+};
+use crate::service::verification_pin::{
+    consume_verification_pin, create_verification_pin, get_active_verification_pin,
+    get_latest_verification_pin, register_verification_attempt,
+};
 use crate::{
     app_state::SharedAppState,
     config::CONFIG,
-    constants::VERIFICATION_PIN_EXPIRATION_TIME,
-    core::utils::{jwt, pin::generate_pin},
+    constants::{
+        MAX_TWO_FACTOR_ATTEMPTS, MAX_VERIFICATION_PIN_ATTEMPTS,
+        TWO_FACTOR_CHALLENGE_EXPIRATION_TIME, VERIFICATION_PIN_ATTEMPTS_WINDOW,
+        VERIFICATION_PIN_EXPIRATION_TIME, VERIFICATION_PIN_RESEND_COOLDOWN,
+    },
+    core::utils::{
+        jwt,
+        pin::{generate_pin, pins_match},
+        token,
+    },
     dtos::auth::{LoginUserDto, VerifyResponseDto},
 };
 
 pub async fn verify_email(
     State(app_state): State<SharedAppState>,
+    client: ClientInfo,
     Json(body): Json<VerifyEmailDto>,
 ) -> Result<impl IntoResponse, HttpError> {
     body.validate().map_err(HttpError::validation_error)?;
 
-    let verification_pin = match get_verification_pin(&app_state.db, &body.email, &body.pin)
+    let attempts = register_verification_attempt(
+        &app_state.db,
+        &body.email,
+        Utc::now() - VERIFICATION_PIN_ATTEMPTS_WINDOW,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if attempts > i64::from(MAX_VERIFICATION_PIN_ATTEMPTS) {
+        return Err(HttpError::too_many_requests(
+            "Too many failed attempts, try again later".to_owned(),
+        ));
+    }
+
+    let verification_pin = match get_active_verification_pin(&app_state.db, &body.email)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?
     {
@@ -41,6 +78,22 @@ pub async fn verify_email(
         ));
     }
 
+    if !pins_match(&verification_pin.pin, &body.pin) {
+        return Err(HttpError::bad_request(
+            "Invalid verification pin".to_owned(),
+        ));
+    }
+
+    let consumed = consume_verification_pin(&app_state.db, &body.email, &verification_pin.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if !consumed {
+        return Err(HttpError::bad_request(
+            "Invalid verification pin".to_owned(),
+        ));
+    }
+
     let user = match get_user_by_email(&app_state.db, &body.email)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?
@@ -49,11 +102,207 @@ pub async fn verify_email(
         None => return Err(HttpError::bad_request("User not found".into())),
     };
 
-    let token = jwt::generate_token(&user.id, &CONFIG.jwt_secret)
+    let user = match user.is_verified {
+        true => user,
+        false => mark_user_verified(&app_state.db, &user.id)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?,
+    };
+
+    let two_factor_enabled = two_factor::is_two_factor_enabled(&app_state.db, &user.id)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-    Ok(Json(VerifyResponseDto { token, user }))
+    if two_factor_enabled {
+        let challenge_token = token::generate_opaque_token();
+
+        let challenge = two_factor::create_two_factor_challenge(
+            &app_state.db,
+            &user.id,
+            &token::hash_token(&challenge_token),
+            Utc::now() + TWO_FACTOR_CHALLENGE_EXPIRATION_TIME,
+        )
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+        return Ok(Json(TwoFactorChallengeResponseDto {
+            two_factor_required: true,
+            challenge_token,
+            expires_at: challenge.expires_at,
+        })
+        .into_response());
+    }
+
+    let response = start_session(&app_state, user, &client).await?;
+
+    Ok(Json(response).into_response())
+}
+
+/// Second step of the login for users with two-factor authentication enabled.
+///
+/// Accepts either a code from the authenticator app or one of the recovery codes.
+pub async fn verify_two_factor(
+    State(app_state): State<SharedAppState>,
+    client: ClientInfo,
+    Json(body): Json<VerifyTwoFactorDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation_error)?;
+
+    let challenge = two_factor::register_two_factor_attempt(
+        &app_state.db,
+        &token::hash_token(&body.challenge_token),
+        MAX_TWO_FACTOR_ATTEMPTS,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let challenge = match challenge {
+        Some(challenge) => challenge,
+        None => {
+            return Err(HttpError::unauthorized(
+                "Invalid or expired challenge, sign in again".to_owned(),
+            ));
+        }
+    };
+
+    let credential = two_factor::get_totp_credential(&app_state.db, &challenge.user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+        .filter(|credential| credential.enabled_at.is_some());
+
+    let credential = match credential {
+        Some(credential) => credential,
+        None => {
+            return Err(HttpError::unauthorized(
+                "Invalid or expired challenge, sign in again".to_owned(),
+            ));
+        }
+    };
+
+    let verified = two_factor::verify_second_factor(&app_state.db, &credential, &body.code)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if !verified {
+        return Err(HttpError::bad_request(
+            "Invalid authentication code".to_owned(),
+        ));
+    }
+
+    let consumed = two_factor::consume_two_factor_challenge(&app_state.db, &challenge.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if !consumed {
+        return Err(HttpError::unauthorized(
+            "Invalid or expired challenge, sign in again".to_owned(),
+        ));
+    }
+
+    let user = match get_user_by_id(&app_state.db, &challenge.user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+    {
+        Some(user) => user,
+        None => return Err(HttpError::bad_request("User not found".into())),
+    };
+
+    let response = start_session(&app_state, user, &client).await?;
+
+    Ok(Json(response))
+}
+
+async fn start_session(
+    app_state: &SharedAppState,
+    user: User,
+    client: &ClientInfo,
+) -> Result<VerifyResponseDto, HttpError> {
+    if let Some(restriction) = user.account_state.restriction() {
+        return Err(HttpError::forbidden(restriction));
+    }
+
+    let refresh_token = token::generate_opaque_token();
+
+    let session = session::create_session(
+        &app_state.db,
+        &user.id,
+        &token::hash_token(&refresh_token),
+        Utc::now() + CONFIG.refresh_token_expiration_duration,
+        client,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let token = jwt::generate_token(&app_state.jwt_keys, &user.id, &session.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(VerifyResponseDto {
+        token,
+        refresh_token,
+        user,
+    })
+}
+
+pub async fn refresh_token(
+    State(app_state): State<SharedAppState>,
+    client: ClientInfo,
+    Json(body): Json<RefreshTokenDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation_error)?;
+
+    let refresh_token = token::generate_opaque_token();
+
+    let rotation = session::rotate_refresh_token(
+        &app_state.db,
+        &token::hash_token(&body.refresh_token),
+        &token::hash_token(&refresh_token),
+        Utc::now() + CONFIG.refresh_token_expiration_duration,
+        &client,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let session = match rotation {
+        RefreshTokenRotation::Rotated(session) => session,
+        RefreshTokenRotation::Reused => {
+            return Err(HttpError::forbidden(
+                "Refresh token reuse detected, session has been revoked".to_owned(),
+            ));
+        }
+        RefreshTokenRotation::Invalid => {
+            return Err(HttpError::unauthorized(
+                "Invalid or expired refresh token".to_owned(),
+            ));
+        }
+    };
+
+    let token = jwt::generate_token(&app_state.jwt_keys, &session.user_id, &session.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(RefreshTokenResponseDto {
+        token,
+        refresh_token,
+    }))
+}
+
+pub async fn logout(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Extension(AuthSession(session_id)): Extension<AuthSession>,
+) -> Result<impl IntoResponse, HttpError> {
+    session::revoke_session(&app_state.db, &user_id, &session_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "success": true,
+            "message": "Logged out successfully"
+        })),
+    ))
 }
 
 pub async fn login(
@@ -63,6 +312,29 @@ pub async fn login(
 ) -> Result<impl IntoResponse, HttpError> {
     body.validate().map_err(HttpError::validation_error)?;
 
+    let latest_pin = get_latest_verification_pin(&app_state.db, &body.email)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if let Some(latest_pin) = latest_pin {
+        let resend_available_at = latest_pin.created_at + VERIFICATION_PIN_RESEND_COOLDOWN;
+
+        if resend_available_at > Utc::now() {
+            return Err(HttpError::too_many_requests(format!(
+                "Please wait {} seconds before requesting a new verification pin",
+                (resend_available_at - Utc::now()).num_seconds() + 1
+            )));
+        }
+    }
+
+    let existing_user = get_user_by_email(&app_state.db, &body.email)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if let Some(restriction) = existing_user.and_then(|user| user.account_state.restriction()) {
+        return Err(HttpError::forbidden(restriction));
+    }
+
     let pin = generate_pin();
 
     let expires_at = Utc::now() + VERIFICATION_PIN_EXPIRATION_TIME;
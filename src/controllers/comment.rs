@@ -1,7 +1,7 @@
 use axum::{
     Extension,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{StatusCode, Uri},
     response::IntoResponse,
 };
 use serde::Deserialize;
@@ -9,10 +9,16 @@ use serde_json::json;
 
 use crate::{
     app_state::SharedAppState,
-    core::{error::http_error::HttpError, extractors::json::Json, layers::auth_layer::AuthUser},
+    core::{
+        error::http_error::HttpError,
+        extractors::{current_user::CurrentUser, json::Json},
+        layers::auth_layer::AuthUser,
+        utils::pagination,
+    },
     dtos::comment::{CreateCommentDto, UpdateCommentDto},
+    models::PostCommentDetails,
     service,
-    types::PaginationQuery,
+    types::{Cursor, PaginationQuery},
 };
 
 #[derive(Deserialize)]
@@ -24,40 +30,86 @@ pub struct GetPostsCommentQuery {
 }
 
 pub async fn get_posts_comments(
+    uri: Uri,
     State(app_state): State<SharedAppState>,
     Path(post_id): Path<String>,
     Query(mut query): Query<GetPostsCommentQuery>,
 ) -> Result<impl IntoResponse, HttpError> {
-    if let Some(parent_id) = query.parent_id.as_ref() {
-        if parent_id.is_empty() {
-            query.parent_id = None
-        }
+    if let Some(parent_id) = query.parent_id.as_ref()
+        && parent_id.is_empty()
+    {
+        query.parent_id = None
     }
 
+    let page = query
+        .pagination
+        .page()
+        .ok_or_else(|| HttpError::bad_request("Invalid cursor".to_string()))?;
+
     let comments = service::comment::get_posts_comments(
         &app_state.db,
         &post_id,
-        query.pagination.offset,
+        &page,
         query.pagination.limit,
-        query.parent_id,
+        query.parent_id.clone(),
     )
     .await
     .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-    Ok(Json(comments))
+    let total = match query.pagination.include_total {
+        true => Some(
+            service::comment::count_posts_comments(
+                &app_state.db,
+                &post_id,
+                query.parent_id.as_deref(),
+            )
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?,
+        ),
+        false => None,
+    };
+
+    Ok(pagination::paginated(
+        &uri,
+        comments,
+        &page,
+        query.pagination.limit,
+        total,
+        comment_cursor,
+    ))
 }
 
 pub async fn get_user_comments(
+    uri: Uri,
     State(app_state): State<SharedAppState>,
     Path(user_id): Path<String>,
     Query(query): Query<PaginationQuery>,
 ) -> Result<impl IntoResponse, HttpError> {
-    let comments =
-        service::comment::get_user_comments(&app_state.db, &user_id, query.offset, query.limit)
-            .await
-            .map_err(|e| HttpError::server_error(e.to_string()))?;
+    let page = query
+        .page()
+        .ok_or_else(|| HttpError::bad_request("Invalid cursor".to_string()))?;
+
+    let comments = service::comment::get_user_comments(&app_state.db, &user_id, &page, query.limit)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-    Ok(Json(comments))
+    let total = match query.include_total {
+        true => Some(
+            service::comment::count_user_comments(&app_state.db, &user_id)
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?,
+        ),
+        false => None,
+    };
+
+    Ok(pagination::paginated(
+        &uri,
+        comments,
+        &page,
+        query.limit,
+        total,
+        comment_cursor,
+    ))
 }
 
 pub async fn create_comment(
@@ -90,10 +142,10 @@ pub async fn update_comment(
 
 pub async fn delete_comment(
     State(app_state): State<SharedAppState>,
-    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    current_user: CurrentUser,
     Path(comment_id): Path<String>,
 ) -> Result<impl IntoResponse, HttpError> {
-    service::comment::delete_comment(&app_state.db, &user_id, &comment_id)
+    service::comment::delete_comment(&app_state.db, &current_user, &comment_id)
         .await
         .map_err(|e| match e {
             sqlx::Error::RowNotFound => HttpError::not_found("Comment not found".into()),
@@ -108,3 +160,7 @@ pub async fn delete_comment(
         })),
     ))
 }
+
+fn comment_cursor(comment: &PostCommentDetails) -> Cursor {
+    Cursor::new(comment.comment.created_at, &comment.comment.id)
+}
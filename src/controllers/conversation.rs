@@ -0,0 +1,280 @@
+use axum::{
+    Extension,
+    extract::{Path, Query, State},
+    http::{StatusCode, Uri},
+    response::IntoResponse,
+};
+use serde_json::json;
+use validator::Validate;
+
+use crate::{
+    app_state::SharedAppState,
+    constants::{MAX_CONVERSATION_PARTICIPANTS, MAX_MESSAGE_ATTACHMENTS},
+    core::{
+        error::http_error::HttpError, extractors::json::Json, layers::auth_layer::AuthUser,
+        utils::pagination,
+    },
+    dtos::conversation::{CreateConversationDto, SendMessageDto, UpdateMessageDto},
+    service,
+    types::{Cursor, Page, PaginationQuery},
+};
+
+pub async fn create_conversation(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Json(body): Json<CreateConversationDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation_error)?;
+
+    let mut participant_ids: Vec<String> = body
+        .participant_ids
+        .into_iter()
+        .filter(|id| *id != user_id)
+        .collect();
+
+    participant_ids.sort();
+    participant_ids.dedup();
+
+    if participant_ids.is_empty() {
+        return Err(HttpError::bad_request(
+            "A conversation needs at least one other participant".to_string(),
+        ));
+    }
+
+    if participant_ids.len() >= MAX_CONVERSATION_PARTICIPANTS {
+        return Err(HttpError::bad_request(format!(
+            "A conversation can have at most {MAX_CONVERSATION_PARTICIPANTS} participants"
+        )));
+    }
+
+    if participant_ids.len() == 1 && body.title.is_some() {
+        return Err(HttpError::bad_request(
+            "Only group conversations have a title".to_string(),
+        ));
+    }
+
+    let conversation_id = service::conversation::create_conversation(
+        &app_state.db,
+        &user_id,
+        &participant_ids,
+        body.title.as_deref(),
+    )
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => HttpError::not_found("User not found".into()),
+        _ => HttpError::server_error(e.to_string()),
+    })?;
+
+    let conversation =
+        service::conversation::get_conversation(&app_state.db, &user_id, &conversation_id)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?
+            .ok_or_else(|| HttpError::server_error("Conversation not found".to_string()))?;
+
+    Ok((StatusCode::CREATED, Json(conversation)))
+}
+
+/// Conversations move up with every new message, so they are always paginated by
+/// offset and a `cursor` is ignored.
+pub async fn get_conversations(
+    uri: Uri,
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Query(query): Query<PaginationQuery>,
+) -> Result<impl IntoResponse, HttpError> {
+    let conversations = service::conversation::get_conversations(
+        &app_state.db,
+        &user_id,
+        query.offset,
+        query.limit,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = match query.include_total {
+        true => Some(
+            service::conversation::count_conversations(&app_state.db, &user_id)
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?,
+        ),
+        false => None,
+    };
+
+    Ok(pagination::paginated(
+        &uri,
+        conversations,
+        &Page::Offset(query.offset),
+        query.limit,
+        total,
+        |c| Cursor::new(c.conversation.last_message_at, &c.conversation.id),
+    ))
+}
+
+pub async fn get_conversation(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Path(conversation_id): Path<String>,
+) -> Result<impl IntoResponse, HttpError> {
+    let conversation =
+        service::conversation::get_conversation(&app_state.db, &user_id, &conversation_id)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    match conversation {
+        Some(conversation) => Ok(Json(conversation)),
+        None => Err(HttpError::not_found("Conversation not found".into())),
+    }
+}
+
+/// Message history, newest first. Deleted messages are kept in place with an empty
+/// content.
+pub async fn get_messages(
+    uri: Uri,
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Path(conversation_id): Path<String>,
+    Query(query): Query<PaginationQuery>,
+) -> Result<impl IntoResponse, HttpError> {
+    let page = query
+        .page()
+        .ok_or_else(|| HttpError::bad_request("Invalid cursor".to_string()))?;
+
+    let is_participant =
+        service::conversation::is_participant(&app_state.db, &conversation_id, &user_id)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if !is_participant {
+        return Err(HttpError::not_found("Conversation not found".into()));
+    }
+
+    let messages =
+        service::message::get_messages(&app_state.db, &conversation_id, &page, query.limit)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = match query.include_total {
+        true => Some(
+            service::message::count_messages(&app_state.db, &conversation_id)
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?,
+        ),
+        false => None,
+    };
+
+    Ok(pagination::paginated(
+        &uri,
+        messages,
+        &page,
+        query.limit,
+        total,
+        |m| Cursor::new(m.message.created_at, &m.message.id),
+    ))
+}
+
+pub async fn send_message(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Path(conversation_id): Path<String>,
+    Json(body): Json<SendMessageDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation_error)?;
+
+    if body.content.trim().is_empty() && body.attachments.is_empty() {
+        return Err(HttpError::bad_request(
+            "A message needs content or attachments".to_string(),
+        ));
+    }
+
+    if body.attachments.len() > MAX_MESSAGE_ATTACHMENTS {
+        return Err(HttpError::bad_request(format!(
+            "A message can have at most {MAX_MESSAGE_ATTACHMENTS} attachments"
+        )));
+    }
+
+    let attachment_urls: Vec<String> = body
+        .attachments
+        .iter()
+        .map(|attachment| attachment.url.clone())
+        .collect();
+
+    let owns_attachments = service::upload::owns_uploads(&app_state.db, &user_id, &attachment_urls)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if !owns_attachments {
+        return Err(HttpError::bad_request(
+            "Attachments must be uploaded by the sender of the message".to_string(),
+        ));
+    }
+
+    let message = service::message::send_message(&app_state.db, &user_id, &conversation_id, body)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => HttpError::not_found("Conversation not found".into()),
+            _ => HttpError::server_error(e.to_string()),
+        })?;
+
+    Ok((StatusCode::CREATED, Json(message)))
+}
+
+pub async fn update_message(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Path((conversation_id, message_id)): Path<(String, String)>,
+    Json(body): Json<UpdateMessageDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation_error)?;
+
+    let message = service::message::update_message(
+        &app_state.db,
+        &user_id,
+        &conversation_id,
+        &message_id,
+        body,
+    )
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::RowNotFound => HttpError::not_found("Message not found".into()),
+        _ => HttpError::server_error(e.to_string()),
+    })?;
+
+    Ok(Json(message))
+}
+
+pub async fn delete_message(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Path((conversation_id, message_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, HttpError> {
+    service::message::delete_message(&app_state.db, &user_id, &conversation_id, &message_id)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => HttpError::not_found("Message not found".into()),
+            _ => HttpError::server_error(e.to_string()),
+        })?;
+
+    Ok(Json(json!({
+        "success": true,
+        "message": "Message deleted successfully"
+    })))
+}
+
+/// Read receipt for every message of the conversation sent so far.
+pub async fn mark_as_read(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Path(conversation_id): Path<String>,
+) -> Result<impl IntoResponse, HttpError> {
+    let read_at = service::conversation::mark_as_read(&app_state.db, &conversation_id, &user_id)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => HttpError::not_found("Conversation not found".into()),
+            _ => HttpError::server_error(e.to_string()),
+        })?;
+
+    Ok(Json(json!({
+        "success": true,
+        "readAt": read_at
+    })))
+}
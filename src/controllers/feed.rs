@@ -0,0 +1,47 @@
+use axum::{
+    Extension,
+    extract::{Query, State},
+    http::Uri,
+    response::IntoResponse,
+};
+
+use crate::{
+    app_state::SharedAppState,
+    core::{error::http_error::HttpError, layers::auth_layer::AuthUser, utils::pagination},
+    service,
+    types::{Cursor, PaginationQuery},
+};
+
+/// The feed is only walked by cursor, `offset` is ignored.
+pub async fn home_feed(
+    uri: Uri,
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Query(query): Query<PaginationQuery>,
+) -> Result<impl IntoResponse, HttpError> {
+    let page = query
+        .cursor_page()
+        .ok_or_else(|| HttpError::bad_request("Invalid cursor".to_string()))?;
+
+    let posts = service::feed::get_home_feed(&app_state.db, &user_id, &page, query.limit)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = match query.include_total {
+        true => Some(
+            service::feed::count_home_feed(&app_state.db, &user_id)
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?,
+        ),
+        false => None,
+    };
+
+    Ok(pagination::paginated(
+        &uri,
+        posts,
+        &page,
+        query.limit,
+        total,
+        |post| Cursor::new(post.post.created_at, &post.post.id),
+    ))
+}
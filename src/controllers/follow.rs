@@ -0,0 +1,83 @@
+use axum::{
+    Extension,
+    extract::{Path, Query, State},
+    response::IntoResponse,
+};
+use serde_json::json;
+
+use crate::{
+    app_state::SharedAppState,
+    core::{error::http_error::HttpError, extractors::json::Json, layers::auth_layer::AuthUser},
+    service,
+    types::PaginationQuery,
+};
+
+pub async fn follow_user(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(follower_id)): Extension<AuthUser>,
+    Path(user_id): Path<String>,
+) -> Result<impl IntoResponse, HttpError> {
+    if follower_id == user_id {
+        return Err(HttpError::bad_request(
+            "You can't follow yourself".to_string(),
+        ));
+    }
+
+    let user = service::user::get_user_by_id(&app_state.db, &user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if user.is_none_or(|user| user.deleted_at.is_some()) {
+        return Err(HttpError::not_found("User not found".into()));
+    }
+
+    service::follow::follow_user(&app_state.db, &follower_id, &user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "following": true
+    })))
+}
+
+pub async fn unfollow_user(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(follower_id)): Extension<AuthUser>,
+    Path(user_id): Path<String>,
+) -> Result<impl IntoResponse, HttpError> {
+    service::follow::unfollow_user(&app_state.db, &follower_id, &user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "following": false
+    })))
+}
+
+pub async fn get_followers(
+    State(app_state): State<SharedAppState>,
+    Path(user_id): Path<String>,
+    Query(query): Query<PaginationQuery>,
+) -> Result<impl IntoResponse, HttpError> {
+    let followers =
+        service::follow::get_followers(&app_state.db, &user_id, query.offset, query.limit)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(followers))
+}
+
+pub async fn get_following(
+    State(app_state): State<SharedAppState>,
+    Path(user_id): Path<String>,
+    Query(query): Query<PaginationQuery>,
+) -> Result<impl IntoResponse, HttpError> {
+    let following =
+        service::follow::get_following(&app_state.db, &user_id, query.offset, query.limit)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(following))
+}
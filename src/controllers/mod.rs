@@ -1,5 +1,18 @@
+pub mod account;
+pub mod admin;
+pub mod api_key;
 pub mod auth;
 pub mod comment;
+pub mod conversation;
+pub mod feed;
+pub mod follow;
+pub mod notification;
 pub mod post;
+pub mod profile;
+pub mod search;
+pub mod stream;
+pub mod tag;
+pub mod two_factor;
 pub mod upload;
 pub mod user;
+pub mod well_known;
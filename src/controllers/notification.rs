@@ -0,0 +1,217 @@
+use axum::{
+    Extension,
+    extract::{Query, State},
+    http::Uri,
+    response::{Html, IntoResponse},
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    app_state::SharedAppState,
+    core::{
+        error::http_error::HttpError,
+        extractors::json::Json,
+        layers::auth_layer::AuthUser,
+        utils::{
+            pagination,
+            unsubscribe::{self, UnsubscribeScope},
+        },
+    },
+    dtos::notification::{
+        MarkNotificationsReadDto, NotificationPreferencesDto, UpdateNotificationPreferencesDto,
+    },
+    models::DigestFrequency,
+    service,
+    types::{Cursor, Page, PaginationQuery},
+};
+
+#[derive(Deserialize)]
+pub struct GetNotificationsQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    unread: Option<String>,
+}
+
+/// Notifications move up when new events join them, so they are always paginated by
+/// offset and a `cursor` is ignored.
+pub async fn get_notifications(
+    uri: Uri,
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Query(query): Query<GetNotificationsQuery>,
+) -> Result<impl IntoResponse, HttpError> {
+    let unread_only = matches!(query.unread.as_deref(), Some("true" | "1"));
+
+    let notifications = service::notification::get_notifications(
+        &app_state.db,
+        &user_id,
+        unread_only,
+        query.pagination.offset,
+        query.pagination.limit,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = match query.pagination.include_total {
+        true => Some(
+            service::notification::count_notifications(&app_state.db, &user_id, unread_only)
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?,
+        ),
+        false => None,
+    };
+
+    Ok(pagination::paginated(
+        &uri,
+        notifications,
+        &Page::Offset(query.pagination.offset),
+        query.pagination.limit,
+        total,
+        |n| Cursor::new(n.notification.updated_at, &n.notification.id),
+    ))
+}
+
+pub async fn get_unread_count(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, HttpError> {
+    let count = service::notification::count_notifications(&app_state.db, &user_id, true)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(json!({ "count": count })))
+}
+
+pub async fn mark_as_read(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Json(body): Json<MarkNotificationsReadDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    let updated = service::notification::mark_as_read(&app_state.db, &user_id, body.ids.as_deref())
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(json!({
+        "success": true,
+        "updated": updated
+    })))
+}
+
+pub async fn get_preferences(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, HttpError> {
+    let preferences = find_preferences(&app_state, &user_id).await?;
+
+    Ok(Json(preferences))
+}
+
+pub async fn update_preferences(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Json(body): Json<UpdateNotificationPreferencesDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    if let Some(deliveries) = &body.deliveries {
+        service::notification_preference::set_deliveries(&app_state.db, &user_id, deliveries)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+    }
+
+    if let Some(frequency) = body.digest {
+        service::notification_preference::set_digest_frequency(&app_state.db, &user_id, frequency)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+    }
+
+    let preferences = find_preferences(&app_state, &user_id).await?;
+
+    Ok(Json(preferences))
+}
+
+#[derive(Deserialize)]
+pub struct UnsubscribeQuery {
+    token: String,
+}
+
+/// Target of the links in notification emails, which work without signing in. Link
+/// scanners and prefetchers follow links on their own, so this only asks for a
+/// confirmation that POSTs back to the same URL.
+pub async fn unsubscribe_page(
+    Query(query): Query<UnsubscribeQuery>,
+) -> Result<impl IntoResponse, HttpError> {
+    let (_, scope) = unsubscribe::verify(&query.token).ok_or(HttpError::bad_request(
+        "Invalid unsubscribe link".to_string(),
+    ))?;
+
+    let question = match scope {
+        UnsubscribeScope::Digest => "Stop getting activity digests?".to_string(),
+        UnsubscribeScope::Email(kind) => format!(
+            "Stop getting {} notifications by email? They will still show up in the app.",
+            kind.to_str()
+        ),
+    };
+
+    Ok(html_page(&format!(
+        r#"<p>{question}</p><form method="post"><button type="submit">Unsubscribe</button></form>"#
+    )))
+}
+
+/// Turns off what the unsubscribe link is for. Mail clients offering one-click
+/// unsubscribe (RFC 8058) POST here directly.
+pub async fn unsubscribe(
+    State(app_state): State<SharedAppState>,
+    Query(query): Query<UnsubscribeQuery>,
+) -> Result<impl IntoResponse, HttpError> {
+    let (user_id, scope) = unsubscribe::verify(&query.token).ok_or(HttpError::bad_request(
+        "Invalid unsubscribe link".to_string(),
+    ))?;
+
+    let message = match scope {
+        UnsubscribeScope::Digest => {
+            service::notification_preference::set_digest_frequency(
+                &app_state.db,
+                &user_id,
+                DigestFrequency::None,
+            )
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+            "You won't get activity digests anymore.".to_string()
+        }
+        UnsubscribeScope::Email(kind) => {
+            service::notification_preference::disable_email_delivery(&app_state.db, &user_id, kind)
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+            format!(
+                "You won't get {} notifications by email anymore. They still show up in the app.",
+                kind.to_str()
+            )
+        }
+    };
+
+    Ok(html_page(&format!("<p>{message}</p>")))
+}
+
+/// `body` is written as is, it must not contain anything from the request.
+fn html_page(body: &str) -> Html<String> {
+    Html(format!(
+        r#"<!DOCTYPE html><html><head><meta charset="utf-8"><title>Unsubscribe</title></head><body>{body}</body></html>"#
+    ))
+}
+
+async fn find_preferences(
+    app_state: &SharedAppState,
+    user_id: &str,
+) -> Result<NotificationPreferencesDto, HttpError> {
+    let deliveries = service::notification_preference::get_deliveries(&app_state.db, user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let digest = service::notification_preference::get_digest_frequency(&app_state.db, user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(NotificationPreferencesDto { deliveries, digest })
+}
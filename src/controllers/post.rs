@@ -1,7 +1,7 @@
 use axum::{
     Extension,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{StatusCode, Uri},
     response::IntoResponse,
 };
 use serde_json::json;
@@ -9,10 +9,19 @@ use validator::Validate;
 
 use crate::{
     app_state::SharedAppState,
-    core::{error::http_error::HttpError, extractors::json::Json, layers::auth_layer::AuthUser},
+    core::{
+        error::http_error::HttpError,
+        extractors::{
+            current_user::{CurrentUser, OptionalAuthUser},
+            json::Json,
+        },
+        layers::auth_layer::AuthUser,
+        utils::pagination,
+    },
     dtos::post::{CreatePostDto, CreatePostResponseDto, UpdatePostDto},
+    models::PostDetails,
     service,
-    types::PaginationQuery,
+    types::{Cursor, PaginationQuery},
 };
 
 pub async fn create_post(
@@ -22,6 +31,18 @@ pub async fn create_post(
 ) -> Result<impl IntoResponse, HttpError> {
     body.validate().map_err(HttpError::validation_error)?;
 
+    let media_urls: Vec<String> = body.media.iter().map(|media| media.url.clone()).collect();
+
+    let owns_media = service::upload::owns_uploads(&app_state.db, &user_id, &media_urls)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if !owns_media {
+        return Err(HttpError::bad_request(
+            "Media must be uploaded by the author of the post".to_string(),
+        ));
+    }
+
     let (post, post_media_list) = service::post::create_post(&app_state.db, &user_id, body)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
@@ -33,33 +54,127 @@ pub async fn create_post(
 }
 
 pub async fn find_posts(
+    uri: Uri,
     Query(query): Query<PaginationQuery>,
+    OptionalAuthUser(viewer_id): OptionalAuthUser,
     State(app_state): State<SharedAppState>,
 ) -> Result<impl IntoResponse, HttpError> {
-    let posts = service::post::find_posts(&app_state.db, query.offset, query.limit)
+    let page = query
+        .page()
+        .ok_or_else(|| HttpError::bad_request("Invalid cursor".to_string()))?;
+
+    let posts = service::post::find_posts(&app_state.db, viewer_id.as_deref(), &page, query.limit)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-    Ok(Json(posts))
+    let total = match query.include_total {
+        true => Some(
+            service::post::count_posts(&app_state.db)
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?,
+        ),
+        false => None,
+    };
+
+    Ok(pagination::paginated(
+        &uri,
+        posts,
+        &page,
+        query.limit,
+        total,
+        post_cursor,
+    ))
 }
 
 pub async fn find_user_posts(
+    uri: Uri,
     Path(user_id): Path<String>,
     Query(query): Query<PaginationQuery>,
+    OptionalAuthUser(viewer_id): OptionalAuthUser,
     State(app_state): State<SharedAppState>,
 ) -> Result<impl IntoResponse, HttpError> {
-    let posts = service::post::find_user_posts(&app_state.db, &user_id, query.offset, query.limit)
-        .await
-        .map_err(|e| HttpError::server_error(e.to_string()))?;
+    let page = query
+        .page()
+        .ok_or_else(|| HttpError::bad_request("Invalid cursor".to_string()))?;
+
+    let posts = service::post::find_user_posts(
+        &app_state.db,
+        viewer_id.as_deref(),
+        &user_id,
+        &page,
+        query.limit,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = match query.include_total {
+        true => Some(
+            service::post::count_user_posts(&app_state.db, &user_id)
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?,
+        ),
+        false => None,
+    };
+
+    Ok(pagination::paginated(
+        &uri,
+        posts,
+        &page,
+        query.limit,
+        total,
+        post_cursor,
+    ))
+}
 
-    Ok(Json(posts))
+pub async fn find_tag_posts(
+    uri: Uri,
+    Path(tag): Path<String>,
+    Query(query): Query<PaginationQuery>,
+    OptionalAuthUser(viewer_id): OptionalAuthUser,
+    State(app_state): State<SharedAppState>,
+) -> Result<impl IntoResponse, HttpError> {
+    let page = query
+        .page()
+        .ok_or_else(|| HttpError::bad_request("Invalid cursor".to_string()))?;
+
+    // Tags are stored lowercased, `#Rust` and `rust` are the same tag
+    let tag = tag.trim_start_matches('#').to_lowercase();
+
+    let posts = service::post::find_tag_posts(
+        &app_state.db,
+        viewer_id.as_deref(),
+        &tag,
+        &page,
+        query.limit,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = match query.include_total {
+        true => Some(
+            service::post::count_tag_posts(&app_state.db, &tag)
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?,
+        ),
+        false => None,
+    };
+
+    Ok(pagination::paginated(
+        &uri,
+        posts,
+        &page,
+        query.limit,
+        total,
+        post_cursor,
+    ))
 }
 
 pub async fn find_post_by_id(
     Path(post_id): Path<String>,
+    OptionalAuthUser(viewer_id): OptionalAuthUser,
     State(app_state): State<SharedAppState>,
 ) -> Result<impl IntoResponse, HttpError> {
-    let post = service::post::find_post_by_id(&app_state.db, &post_id)
+    let post = service::post::find_post_by_id(&app_state.db, viewer_id.as_deref(), &post_id)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
@@ -89,10 +204,10 @@ pub async fn update_post(
 
 pub async fn delete_post(
     Path(post_id): Path<String>,
-    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    current_user: CurrentUser,
     State(app_state): State<SharedAppState>,
 ) -> Result<impl IntoResponse, HttpError> {
-    let post = service::post::delete_post(&app_state.db, &user_id, &post_id)
+    let post = service::post::delete_post(&app_state.db, &current_user, &post_id)
         .await
         .map_err(|e| HttpError::server_error(e.to_string()))?;
 
@@ -125,3 +240,7 @@ pub async fn like_post(
         "liked": is_liked
     })))
 }
+
+fn post_cursor(post: &PostDetails) -> Cursor {
+    Cursor::new(post.post.created_at, &post.post.id)
+}
@@ -0,0 +1,73 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+};
+use serde::Deserialize;
+
+use crate::{
+    app_state::SharedAppState,
+    constants::{DEFAULT_USER_SEARCH_LIMIT, MAX_USER_SEARCH_LIMIT},
+    core::{
+        error::http_error::HttpError,
+        extractors::{current_user::OptionalAuthUser, json::Json},
+    },
+    service,
+};
+
+#[derive(Deserialize)]
+pub struct UserSearchQuery {
+    q: String,
+    limit: Option<i64>,
+}
+
+pub async fn get_profile(
+    State(app_state): State<SharedAppState>,
+    Path(user_id): Path<String>,
+) -> Result<impl IntoResponse, HttpError> {
+    let profile = service::profile::get_public_profile_by_id(&app_state.db, &user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    match profile {
+        Some(profile) => Ok(Json(profile)),
+        None => Err(HttpError::not_found("User not found".into())),
+    }
+}
+
+pub async fn get_profile_by_username(
+    State(app_state): State<SharedAppState>,
+    Path(username): Path<String>,
+) -> Result<impl IntoResponse, HttpError> {
+    let profile = service::profile::get_public_profile_by_username(&app_state.db, &username)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    match profile {
+        Some(profile) => Ok(Json(profile)),
+        None => Err(HttpError::not_found("User not found".into())),
+    }
+}
+
+/// Username autocomplete, a leading `@` is ignored so mentions can be searched as typed.
+pub async fn search_users(
+    State(app_state): State<SharedAppState>,
+    OptionalAuthUser(viewer_id): OptionalAuthUser,
+    Query(query): Query<UserSearchQuery>,
+) -> Result<impl IntoResponse, HttpError> {
+    let q = query.q.trim().trim_start_matches('@');
+
+    if q.is_empty() {
+        return Err(HttpError::bad_request("Search query is required".into()));
+    }
+
+    let limit = match query.limit {
+        Some(limit) if limit > 0 => limit.min(MAX_USER_SEARCH_LIMIT),
+        _ => DEFAULT_USER_SEARCH_LIMIT,
+    };
+
+    let users = service::user::search_users(&app_state.db, viewer_id.as_deref(), q, limit)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(users))
+}
@@ -0,0 +1,65 @@
+use axum::{
+    extract::{Query, State},
+    http::Uri,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::{
+    app_state::SharedAppState,
+    core::{error::http_error::HttpError, utils::pagination},
+    dtos::search::SearchQueryDto,
+    service,
+    types::{Cursor, Page, PaginationQuery},
+};
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    #[serde(flatten)]
+    pagination: PaginationQuery,
+    #[serde(flatten)]
+    search: SearchQueryDto,
+}
+
+/// Hits are ordered by relevance rather than by date, so they are always paginated
+/// by offset and a `cursor` is ignored.
+pub async fn search(
+    uri: Uri,
+    State(app_state): State<SharedAppState>,
+    Query(mut query): Query<SearchQuery>,
+) -> Result<impl IntoResponse, HttpError> {
+    query.search.q = query.search.q.trim().to_string();
+
+    query
+        .search
+        .validate()
+        .map_err(HttpError::validation_error)?;
+
+    let hits = service::search::search(
+        &app_state.db,
+        &query.search,
+        query.pagination.offset,
+        query.pagination.limit,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let total = match query.pagination.include_total {
+        true => Some(
+            service::search::count_search_hits(&app_state.db, &query.search)
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?,
+        ),
+        false => None,
+    };
+
+    Ok(pagination::paginated(
+        &uri,
+        hits,
+        &Page::Offset(query.pagination.offset),
+        query.pagination.limit,
+        total,
+        |hit| Cursor::new(hit.created_at, &hit.id),
+    ))
+}
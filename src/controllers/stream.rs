@@ -0,0 +1,148 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use axum::{
+    Extension,
+    extract::{Query, State},
+    response::{
+        IntoResponse,
+        sse::{Event, KeepAlive, Sse},
+    },
+};
+use chrono::Utc;
+use serde::Deserialize;
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc,
+};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{
+    app_state::SharedAppState,
+    constants::MAX_STREAM_WATCHED_POSTS,
+    core::{
+        error::http_error::HttpError,
+        layers::auth_layer::{AuthSession, AuthTokenExpiry, AuthUser},
+        services::event_hub::{StreamClose, StreamEvent},
+    },
+    service,
+};
+
+#[derive(Deserialize)]
+pub struct StreamQuery {
+    /// Comma separated ids of the posts to receive new comments and like counts of.
+    posts: Option<String>,
+}
+
+/// Server-Sent Events stream of the user's notifications and direct messages, and of
+/// activity on the watched posts. Each event is named after its `type`, a `lagged`
+/// event carrying the number of skipped events is sent when the client couldn't keep
+/// up.
+///
+/// The stream ends with a `closed` event carrying the reason when the access token
+/// expires, the session is revoked or the account is restricted, as the checks of the
+/// auth layer only ran when it was opened. Watching other posts takes a new
+/// connection.
+pub async fn stream(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Extension(AuthSession(session_id)): Extension<AuthSession>,
+    Extension(AuthTokenExpiry(expires_at)): Extension<AuthTokenExpiry>,
+    Query(query): Query<StreamQuery>,
+) -> Result<impl IntoResponse, HttpError> {
+    let watched_posts: HashSet<String> = query
+        .posts
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .filter(|post_id| !post_id.is_empty())
+        .map(str::to_string)
+        .collect();
+
+    if watched_posts.len() > MAX_STREAM_WATCHED_POSTS {
+        return Err(HttpError::bad_request(format!(
+            "At most {MAX_STREAM_WATCHED_POSTS} posts can be watched"
+        )));
+    }
+
+    let receiver = app_state.events.subscribe();
+
+    // The session may have been revoked since the auth layer checked it, before the
+    // stream could see the event
+    let session_owner = service::session::touch_active_session(&app_state.db, &session_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    match session_owner {
+        Some(owner) => {
+            if let Some(restriction) = owner.account_state.restriction() {
+                return Err(HttpError::forbidden(restriction));
+            }
+        }
+        None => {
+            return Err(HttpError::unauthorized(
+                "Session has been revoked".to_string(),
+            ));
+        }
+    }
+
+    let expires_in = (expires_at - Utc::now()).to_std().unwrap_or_default();
+
+    // Events are forwarded one at a time, so the backlog of a slow client stays in
+    // the hub's buffer and still ends up as a `lagged` event
+    let (sender, events) = mpsc::channel(1);
+
+    tokio::spawn(forward_events(
+        receiver,
+        sender,
+        user_id,
+        session_id,
+        watched_posts,
+        expires_in,
+    ));
+
+    Ok(Sse::new(ReceiverStream::new(events)).keep_alive(KeepAlive::default()))
+}
+
+/// Forwards the events meant for the client until it disconnects or the stream has to
+/// be closed.
+async fn forward_events(
+    mut receiver: broadcast::Receiver<Arc<StreamEvent>>,
+    sender: mpsc::Sender<Result<Event, axum::Error>>,
+    user_id: String,
+    session_id: String,
+    watched_posts: HashSet<String>,
+    expires_in: Duration,
+) {
+    let expiry = tokio::time::sleep(expires_in);
+    tokio::pin!(expiry);
+
+    let close = loop {
+        let event = tokio::select! {
+            _ = sender.closed() => return,
+            _ = &mut expiry => break StreamClose::TokenExpired,
+            event = receiver.recv() => event,
+        };
+
+        let event = match event {
+            Ok(event) => match event.closes(&user_id, &session_id) {
+                Some(close) => break close,
+                None if event.is_for(&user_id, &watched_posts) => {
+                    Event::default().event(event.name()).json_data(&*event)
+                }
+                None => continue,
+            },
+            Err(RecvError::Lagged(skipped)) => {
+                Ok(Event::default().event("lagged").data(skipped.to_string()))
+            }
+            Err(RecvError::Closed) => return,
+        };
+
+        if sender.send(event).await.is_err() {
+            return;
+        }
+    };
+
+    let closed = Event::default().event("closed").data(close.to_str());
+
+    let _ = sender.send(Ok(closed)).await;
+}
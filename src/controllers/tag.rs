@@ -0,0 +1,23 @@
+use axum::{extract::State, response::IntoResponse};
+use chrono::Utc;
+
+use crate::{
+    app_state::SharedAppState,
+    constants::{TRENDING_TAGS_LIMIT, TRENDING_TAGS_WINDOW},
+    core::{error::http_error::HttpError, extractors::json::Json},
+    service,
+};
+
+pub async fn get_trending_tags(
+    State(app_state): State<SharedAppState>,
+) -> Result<impl IntoResponse, HttpError> {
+    let tags = service::tag::get_trending_tags(
+        &app_state.db,
+        Utc::now() - TRENDING_TAGS_WINDOW,
+        TRENDING_TAGS_LIMIT,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(tags))
+}
@@ -0,0 +1,227 @@
+use axum::{Extension, extract::State, http::StatusCode, response::IntoResponse};
+use serde_json::json;
+use validator::Validate;
+
+use crate::{
+    app_state::SharedAppState,
+    constants::RECOVERY_CODES_COUNT,
+    core::{
+        error::http_error::HttpError,
+        extractors::json::Json,
+        layers::auth_layer::AuthUser,
+        utils::{token, totp},
+    },
+    dtos::two_factor::{
+        RecoveryCodesResponseDto, TotpEnrollmentResponseDto, TwoFactorCodeDto,
+        TwoFactorStatusResponseDto,
+    },
+    models::TotpCredential,
+    service::{self, two_factor},
+};
+
+pub async fn get_two_factor_status(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, HttpError> {
+    let (enabled, recovery_codes_remaining) = tokio::try_join!(
+        two_factor::is_two_factor_enabled(&app_state.db, &user_id),
+        two_factor::count_unused_recovery_codes(&app_state.db, &user_id),
+    )
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(TwoFactorStatusResponseDto {
+        enabled,
+        recovery_codes_remaining,
+    }))
+}
+
+/// Starts enrolling an authenticator app. Two-factor authentication is only
+/// enabled once a code from the app is confirmed.
+pub async fn enroll_totp(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+) -> Result<impl IntoResponse, HttpError> {
+    let user = match service::user::get_user_by_id(&app_state.db, &user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+    {
+        Some(user) => user,
+        None => return Err(HttpError::not_found("User not found".into())),
+    };
+
+    let secret = totp::generate_secret();
+
+    let credential = two_factor::create_pending_totp_credential(&app_state.db, &user_id, &secret)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if credential.is_none() {
+        return Err(HttpError::conflict(
+            "Two-factor authentication is already enabled".to_owned(),
+        ));
+    }
+
+    let provisioning_uri = totp::provisioning_uri(&secret, &user.email);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(TotpEnrollmentResponseDto {
+            secret,
+            provisioning_uri,
+        }),
+    ))
+}
+
+/// Enables two-factor authentication and returns the recovery codes. They are only
+/// shown once.
+pub async fn confirm_totp(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Json(body): Json<TwoFactorCodeDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation_error)?;
+
+    let credential = match two_factor::get_totp_credential(&app_state.db, &user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+    {
+        Some(credential) if credential.enabled_at.is_none() => credential,
+        Some(_) => {
+            return Err(HttpError::conflict(
+                "Two-factor authentication is already enabled".to_owned(),
+            ));
+        }
+        None => {
+            return Err(HttpError::bad_request(
+                "Start the enrollment before confirming it".to_owned(),
+            ));
+        }
+    };
+
+    let step = match totp::verify_code(&credential.secret, body.code.trim(), totp::current_step()) {
+        Some(step) => step,
+        None => {
+            return Err(HttpError::bad_request(
+                "Invalid authentication code".to_owned(),
+            ));
+        }
+    };
+
+    let recovery_codes = generate_recovery_codes();
+
+    let recorded = two_factor::record_totp_step(&app_state.db, &user_id, step)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let enabled = recorded
+        && two_factor::enable_totp_credential(
+            &app_state.db,
+            &user_id,
+            &hash_recovery_codes(&recovery_codes),
+        )
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if !enabled {
+        return Err(HttpError::bad_request(
+            "Invalid authentication code".to_owned(),
+        ));
+    }
+
+    Ok(Json(RecoveryCodesResponseDto { recovery_codes }))
+}
+
+pub async fn disable_totp(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Json(body): Json<TwoFactorCodeDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation_error)?;
+
+    let credential = get_enabled_credential(&app_state, &user_id).await?;
+
+    verify_code(&app_state, &credential, &body.code).await?;
+
+    two_factor::disable_totp_credential(&app_state.db, &user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "success": true,
+            "message": "Two-factor authentication disabled successfully"
+        })),
+    ))
+}
+
+/// Replaces every recovery code of the user with new ones.
+pub async fn regenerate_recovery_codes(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Json(body): Json<TwoFactorCodeDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation_error)?;
+
+    let credential = get_enabled_credential(&app_state, &user_id).await?;
+
+    verify_code(&app_state, &credential, &body.code).await?;
+
+    let recovery_codes = generate_recovery_codes();
+
+    two_factor::replace_recovery_codes(
+        &app_state.db,
+        &user_id,
+        &hash_recovery_codes(&recovery_codes),
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok(Json(RecoveryCodesResponseDto { recovery_codes }))
+}
+
+async fn get_enabled_credential(
+    app_state: &SharedAppState,
+    user_id: &str,
+) -> Result<TotpCredential, HttpError> {
+    let credential = two_factor::get_totp_credential(&app_state.db, user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    match credential {
+        Some(credential) if credential.enabled_at.is_some() => Ok(credential),
+        _ => Err(HttpError::bad_request(
+            "Two-factor authentication is not enabled".to_owned(),
+        )),
+    }
+}
+
+async fn verify_code(
+    app_state: &SharedAppState,
+    credential: &TotpCredential,
+    code: &str,
+) -> Result<(), HttpError> {
+    let verified = two_factor::verify_second_factor(&app_state.db, credential, code)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    match verified {
+        true => Ok(()),
+        false => Err(HttpError::bad_request(
+            "Invalid authentication code".to_owned(),
+        )),
+    }
+}
+
+fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODES_COUNT)
+        .map(|_| token::generate_recovery_code())
+        .collect()
+}
+
+fn hash_recovery_codes(recovery_codes: &[String]) -> Vec<String> {
+    recovery_codes
+        .iter()
+        .map(|code| token::hash_recovery_code(code))
+        .collect()
+}
@@ -1,13 +1,27 @@
-use axum::{Extension, extract::Multipart, http::StatusCode, response::IntoResponse};
+use axum::{
+    Extension,
+    extract::{Multipart, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
 use axum_extra::{TypedHeader, headers::ContentType};
 use serde_json::json;
 
 use crate::{
-    core::{error::http_error::HttpError, services::storage::UploadOptions},
+    app_state::SharedAppState,
+    core::{
+        error::http_error::HttpError, extractors::current_user::OptionalAuthUser,
+        services::storage::UploadOptions,
+    },
     extensions::StorageServiceExt,
+    service,
 };
 
+/// Anonymous uploads are allowed so a profile image can be sent along a sign up, the
+/// account then claims it.
 pub async fn upload_file(
+    State(app_state): State<SharedAppState>,
+    OptionalAuthUser(user_id): OptionalAuthUser,
     Extension(storage_provider): StorageServiceExt,
     mut multipart: Multipart,
 ) -> Result<impl IntoResponse, HttpError> {
@@ -38,6 +52,10 @@ pub async fn upload_file(
                 .await
                 .map_err(|e| HttpError::server_error(e.to_string()))?;
 
+            service::upload::create_upload(&app_state.db, &url, user_id.as_deref())
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?;
+
             return Ok((
                 StatusCode::CREATED,
                 TypedHeader(ContentType::json()),
@@ -1,13 +1,30 @@
 use crate::core::extractors::json::Json;
-use axum::{Extension, extract::State, response::IntoResponse};
+use axum::{
+    Extension,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::Utc;
+use serde_json::json;
 use validator::Validate;
 
 use crate::{
     app_state::SharedAppState,
+    constants::{
+        MAX_VERIFICATION_PIN_ATTEMPTS, VERIFICATION_PIN_EXPIRATION_TIME,
+        VERIFICATION_PIN_RESEND_COOLDOWN,
+    },
     core::error::http_error::HttpError,
-    core::layers::auth_layer::AuthUser,
-    dtos::user::UpdateProfileDto,
-    service::user::{get_user_by_id, get_user_by_username, update_user},
+    core::layers::auth_layer::{AuthSession, AuthUser},
+    core::utils::pin::{generate_pin, pins_match},
+    dtos::user::{
+        ConfirmEmailChangeDto, RequestEmailChangeDto, SessionResponseDto, UpdateProfileDto,
+    },
+    extensions::MailServiceExt,
+    service::email_change::{self, EmailChange},
+    service::session,
+    service::user::{get_user_by_email, get_user_by_id, get_user_by_username, update_user},
 };
 
 pub async fn whoami(
@@ -36,11 +53,8 @@ pub async fn update_profile(
             .await
             .map_err(|e| HttpError::server_error(e.to_string()))?;
 
-        match maybe_user {
-            Some(_) => {
-                return Err(HttpError::conflict("Username already exists".to_string()));
-            }
-            None => (),
+        if maybe_user.is_some() {
+            return Err(HttpError::conflict("Username already exists".to_string()));
         }
     }
 
@@ -50,3 +64,210 @@ pub async fn update_profile(
 
     Ok(Json(user))
 }
+
+pub async fn get_sessions(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Extension(AuthSession(current_session_id)): Extension<AuthSession>,
+) -> Result<impl IntoResponse, HttpError> {
+    let sessions = session::get_active_user_sessions(&app_state.db, &user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let sessions: Vec<SessionResponseDto> = sessions
+        .into_iter()
+        .map(|session| SessionResponseDto {
+            current: session.id == current_session_id,
+            session,
+        })
+        .collect();
+
+    Ok(Json(sessions))
+}
+
+pub async fn revoke_session(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Path(session_id): Path<String>,
+) -> Result<impl IntoResponse, HttpError> {
+    let session = session::revoke_session(&app_state.db, &user_id, &session_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    match session {
+        Some(_) => Ok((
+            StatusCode::ACCEPTED,
+            Json(json!({
+                "success": true,
+                "message": "Session revoked successfully"
+            })),
+        )),
+        None => Err(HttpError::not_found("Session not found".into())),
+    }
+}
+
+pub async fn revoke_other_sessions(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Extension(AuthSession(current_session_id)): Extension<AuthSession>,
+) -> Result<impl IntoResponse, HttpError> {
+    let revoked_count =
+        session::revoke_other_sessions(&app_state.db, &user_id, &current_session_id)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "success": true,
+            "revoked": revoked_count
+        })),
+    ))
+}
+
+/// Sends a pin to the new address. The email is only changed once the pin is
+/// confirmed, the current address is notified either way.
+pub async fn request_email_change(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Extension(mail_service): MailServiceExt,
+    Json(body): Json<RequestEmailChangeDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation_error)?;
+
+    let (user, existing_user, latest_request) = tokio::try_join!(
+        get_user_by_id(&app_state.db, &user_id),
+        get_user_by_email(&app_state.db, &body.new_email),
+        email_change::get_latest_email_change_request(&app_state.db, &user_id),
+    )
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let user = match user {
+        Some(user) => user,
+        None => return Err(HttpError::not_found("User not found".into())),
+    };
+
+    if user.email == body.new_email {
+        return Err(HttpError::bad_request(
+            "New email must be different from the current one".to_string(),
+        ));
+    }
+
+    if existing_user.is_some() {
+        return Err(HttpError::conflict("Email already exists".to_string()));
+    }
+
+    if let Some(latest_request) = latest_request {
+        let resend_available_at = latest_request.created_at + VERIFICATION_PIN_RESEND_COOLDOWN;
+
+        if resend_available_at > Utc::now() {
+            return Err(HttpError::too_many_requests(format!(
+                "Please wait {} seconds before requesting a new email change",
+                (resend_available_at - Utc::now()).num_seconds() + 1
+            )));
+        }
+    }
+
+    let request = email_change::create_email_change_request(
+        &app_state.db,
+        &user_id,
+        &body.new_email,
+        &generate_pin(),
+        Utc::now() + VERIFICATION_PIN_EXPIRATION_TIME,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    mail_service
+        .send_email_change_mail(request.new_email.clone(), request.pin)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if let Err(e) = mail_service
+        .send_email_change_requested_mail(user.email, &request.new_email)
+        .await
+    {
+        tracing::error!("Failed to notify {} about an email change: {}", user_id, e);
+    }
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "success": true,
+            "message": "A verification pin has been sent to the new email address",
+            "expiresAt": request.expires_at,
+        })),
+    ))
+}
+
+pub async fn confirm_email_change(
+    State(app_state): State<SharedAppState>,
+    Extension(AuthUser(user_id)): Extension<AuthUser>,
+    Extension(mail_service): MailServiceExt,
+    Json(body): Json<ConfirmEmailChangeDto>,
+) -> Result<impl IntoResponse, HttpError> {
+    body.validate().map_err(HttpError::validation_error)?;
+
+    let request = match email_change::get_active_email_change_request(&app_state.db, &user_id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?
+    {
+        Some(request) => request,
+        None => {
+            return Err(HttpError::bad_request(
+                "No pending email change".to_string(),
+            ));
+        }
+    };
+
+    if request.expires_at < Utc::now() {
+        return Err(HttpError::bad_request(
+            "Verification pin expired".to_string(),
+        ));
+    }
+
+    let attempts = email_change::register_email_change_attempt(
+        &app_state.db,
+        &request.id,
+        MAX_VERIFICATION_PIN_ATTEMPTS,
+    )
+    .await
+    .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    if attempts.is_none() {
+        return Err(HttpError::too_many_requests(
+            "Too many failed attempts, request a new email change".to_string(),
+        ));
+    }
+
+    if !pins_match(&request.pin, &body.pin) {
+        return Err(HttpError::bad_request(
+            "Invalid verification pin".to_string(),
+        ));
+    }
+
+    let change = email_change::confirm_email_change(&app_state.db, &user_id, &request.id)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    let (user, old_email) = match change {
+        EmailChange::Changed { user, old_email } => (user, old_email),
+        EmailChange::EmailTaken => {
+            return Err(HttpError::conflict("Email already exists".to_string()));
+        }
+        EmailChange::Invalid => {
+            return Err(HttpError::bad_request(
+                "Invalid verification pin".to_string(),
+            ));
+        }
+    };
+
+    if let Err(e) = mail_service
+        .send_email_changed_mail(old_email, &user.email)
+        .await
+    {
+        tracing::error!("Failed to notify {} about an email change: {}", user_id, e);
+    }
+
+    Ok(Json(user))
+}
@@ -0,0 +1,16 @@
+use axum::{extract::State, http::header::CACHE_CONTROL, response::IntoResponse};
+
+use crate::{
+    app_state::SharedAppState,
+    core::{extractors::json::Json, services::jwt_keys::JWKS_MAX_AGE},
+};
+
+pub async fn jwks(State(app_state): State<SharedAppState>) -> impl IntoResponse {
+    (
+        [(
+            CACHE_CONTROL,
+            format!("public, max-age={}", JWKS_MAX_AGE.as_secs()),
+        )],
+        Json(app_state.jwt_keys.jwks()),
+    )
+}
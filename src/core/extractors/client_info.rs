@@ -0,0 +1,140 @@
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+};
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts},
+    http::{HeaderMap, header::USER_AGENT, request::Parts},
+};
+
+use crate::config::CONFIG;
+
+/// User agent and ip address of the client that sent the request.
+///
+/// The ip address is the peer address of the connection. `X-Forwarded-For` is only
+/// read when the peer is one of the trusted proxies, whose right-most hop that isn't
+/// itself a trusted proxy is the client, as entries on its left are set by the client.
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_agent = parts
+            .headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+
+        let peer_ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+
+        let ip_address = peer_ip
+            .map(|ip| client_ip(ip, &parts.headers, &CONFIG.trusted_proxies))
+            .map(|ip| ip.to_string());
+
+        Ok(Self {
+            user_agent,
+            ip_address,
+        })
+    }
+}
+
+fn client_ip(peer_ip: IpAddr, headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> IpAddr {
+    if !trusted_proxies.contains(&peer_ip) {
+        return peer_ip;
+    }
+
+    let hops: Option<Vec<IpAddr>> = headers
+        .get_all("X-Forwarded-For")
+        .iter()
+        .map(|value| value.to_str().ok())
+        .collect::<Option<Vec<&str>>>()
+        .and_then(|values| {
+            values
+                .iter()
+                .flat_map(|value| value.split(','))
+                .map(|hop| hop.trim().parse::<IpAddr>().ok())
+                .collect()
+        });
+
+    // A malformed header can't be trusted to point at the client
+    let Some(hops) = hops else {
+        return peer_ip;
+    };
+
+    let mut client_ip = peer_ip;
+    for hop in hops.into_iter().rev() {
+        client_ip = hop;
+        if !trusted_proxies.contains(&hop) {
+            break;
+        }
+    }
+
+    client_ip
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    const PROXY: &str = "10.0.0.1";
+    const CLIENT: &str = "203.0.113.7";
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    fn forwarded_for(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append("X-Forwarded-For", HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn ignores_forwarded_for_from_untrusted_peers() {
+        let headers = forwarded_for(&["198.51.100.1"]);
+
+        assert_eq!(client_ip(ip(CLIENT), &headers, &[]), ip(CLIENT));
+        assert_eq!(client_ip(ip(CLIENT), &headers, &[ip(PROXY)]), ip(CLIENT));
+    }
+
+    #[test]
+    fn takes_the_right_most_untrusted_hop() {
+        let headers = forwarded_for(&[&format!("198.51.100.1, {CLIENT}")]);
+
+        assert_eq!(client_ip(ip(PROXY), &headers, &[ip(PROXY)]), ip(CLIENT));
+    }
+
+    #[test]
+    fn skips_chained_trusted_proxies() {
+        let proxies = [ip(PROXY), ip("10.0.0.2")];
+        let headers = forwarded_for(&["198.51.100.1", &format!("{CLIENT}, 10.0.0.2")]);
+
+        assert_eq!(client_ip(ip(PROXY), &headers, &proxies), ip(CLIENT));
+    }
+
+    #[test]
+    fn falls_back_to_the_peer_without_a_valid_header() {
+        let proxies = [ip(PROXY)];
+
+        assert_eq!(client_ip(ip(PROXY), &HeaderMap::new(), &proxies), ip(PROXY));
+        assert_eq!(
+            client_ip(ip(PROXY), &forwarded_for(&["not-an-ip"]), &proxies),
+            ip(PROXY)
+        );
+    }
+}
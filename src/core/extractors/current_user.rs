@@ -0,0 +1,111 @@
+use std::{convert::Infallible, marker::PhantomData};
+
+use axum::{extract::FromRequestParts, http::request::Parts};
+
+use crate::{
+    core::{
+        error::http_error::HttpError,
+        layers::auth_layer::{AuthRole, AuthUser},
+    },
+    models::UserRole,
+};
+
+/// The authenticated user together with their role.
+///
+/// Services take this instead of a bare user id whenever moderators are allowed
+/// to act on content they don't own.
+#[derive(Clone)]
+pub struct CurrentUser {
+    pub id: String,
+    pub role: UserRole,
+}
+
+impl CurrentUser {
+    pub fn can_moderate(&self) -> bool {
+        self.role >= UserRole::Moderator
+    }
+}
+
+impl<S> FromRequestParts<S> for CurrentUser
+where
+    S: Send + Sync,
+{
+    type Rejection = HttpError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_id = parts.extensions.get::<AuthUser>();
+        let role = parts.extensions.get::<AuthRole>();
+
+        match (user_id, role) {
+            (Some(AuthUser(id)), Some(AuthRole(role))) => Ok(Self {
+                id: id.clone(),
+                role: *role,
+            }),
+            _ => Err(HttpError::unauthorized("Unauthorized".to_string())),
+        }
+    }
+}
+
+/// Id of the viewer on routes that don't require authentication, `None` for
+/// anonymous requests.
+pub struct OptionalAuthUser(pub Option<String>);
+
+impl<S> FromRequestParts<S> for OptionalAuthUser
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_id = parts
+            .extensions
+            .get::<AuthUser>()
+            .map(|AuthUser(id)| id.clone());
+
+        Ok(Self(user_id))
+    }
+}
+
+pub trait RoleRequirement {
+    const ROLE: UserRole;
+}
+
+pub struct Moderator;
+
+impl RoleRequirement for Moderator {
+    const ROLE: UserRole = UserRole::Moderator;
+}
+
+pub struct Admin;
+
+impl RoleRequirement for Admin {
+    const ROLE: UserRole = UserRole::Admin;
+}
+
+/// Rejects the request unless the user has at least the role `R` requires.
+///
+/// ```ignore
+/// pub async fn handler(RequireRole(admin, _): RequireRole<Admin>) { ... }
+/// ```
+pub struct RequireRole<R: RoleRequirement>(pub CurrentUser, pub PhantomData<R>);
+
+impl<S, R> FromRequestParts<S> for RequireRole<R>
+where
+    S: Send + Sync,
+    R: RoleRequirement,
+{
+    type Rejection = HttpError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user = CurrentUser::from_request_parts(parts, state).await?;
+
+        if user.role < R::ROLE {
+            return Err(HttpError::forbidden(format!(
+                "This action requires the {} role",
+                R::ROLE.to_str()
+            )));
+        }
+
+        Ok(Self(user, PhantomData))
+    }
+}
@@ -1 +1,3 @@
+pub mod client_info;
+pub mod current_user;
 pub mod json;
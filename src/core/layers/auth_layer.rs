@@ -5,77 +5,145 @@ use std::{
 
 use axum::{
     body::Body,
-    http::{HeaderMap, HeaderValue, Method, Request, Response},
+    http::{HeaderMap, HeaderValue, Method, Request, Response, header::AUTHORIZATION},
     response::IntoResponse,
 };
+use chrono::{DateTime, Utc};
 use tower::{Layer, Service};
 
 use crate::core::error::http_error::HttpError;
-use crate::{config::CONFIG, core::utils::jwt};
+use crate::models::{ApiKey, ApiKeyScope, UserRole};
+use crate::{
+    app_state::SharedAppState,
+    constants::API_KEY_PREFIX,
+    core::utils::{jwt, token},
+    service,
+};
 
-#[derive(Debug, Clone, Default)]
-pub struct ExcludedPaths {
-    post: matchit::Router<()>,
-    get: matchit::Router<()>,
-    put: matchit::Router<()>,
-    patch: matchit::Router<()>,
-    delete: matchit::Router<()>,
+/// Who may call a route. Every route in the api declares one next to its handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthPolicy {
+    public: bool,
+    api_key_scope: Option<ApiKeyScope>,
 }
 
-impl ExcludedPaths {
-    pub fn new() -> Self {
+impl AuthPolicy {
+    /// Anyone may call the route. The viewer is still identified when a valid
+    /// token is sent.
+    pub const fn public() -> Self {
         Self {
-            post: Self::from(&["/auth/login", "/auth/verify", "/upload"]),
-            get: Self::from(&[
-                "/",
-                "/posts",
-                "/posts/{post_id}",
-                "/posts/user/{user_id}",
-                "/comments/post/{post_id}",
-                "/comments/user/{user_id}",
-            ]),
-            put: Self::from(&[]),
-            patch: Self::from(&[]),
-            delete: Self::from(&[]),
+            public: true,
+            api_key_scope: None,
         }
     }
 
-    fn from(paths: &[&str]) -> matchit::Router<()> {
-        let mut router = matchit::Router::new();
+    /// Only authenticated users may call the route.
+    pub const fn authenticated() -> Self {
+        Self {
+            public: false,
+            api_key_scope: None,
+        }
+    }
 
-        paths.iter().for_each(|value| {
-            router.insert(value.to_string(), ()).unwrap();
-        });
+    /// Allows api keys carrying `scope` on the route. Without a scope a route can
+    /// only be accessed with a session token.
+    pub const fn api_key(mut self, scope: ApiKeyScope) -> Self {
+        self.api_key_scope = Some(scope);
+        self
+    }
+}
+
+/// Auth policy of every route, looked up by method and path.
+#[derive(Debug, Clone, Default)]
+pub struct RoutePolicies {
+    post: matchit::Router<AuthPolicy>,
+    get: matchit::Router<AuthPolicy>,
+    put: matchit::Router<AuthPolicy>,
+    patch: matchit::Router<AuthPolicy>,
+    delete: matchit::Router<AuthPolicy>,
+}
+
+impl RoutePolicies {
+    pub fn insert(
+        &mut self,
+        method: &Method,
+        path: &str,
+        policy: AuthPolicy,
+    ) -> Result<(), String> {
+        let router = match *method {
+            Method::GET => &mut self.get,
+            Method::POST => &mut self.post,
+            Method::PUT => &mut self.put,
+            Method::PATCH => &mut self.patch,
+            Method::DELETE => &mut self.delete,
+            _ => return Err(format!("{method} {path}: unsupported method")),
+        };
 
         router
+            .insert(path, policy)
+            .map_err(|e| format!("{method} {path}: {e}"))
+    }
+
+    pub fn at(&self, method: &Method, path: &str) -> Option<AuthPolicy> {
+        let router = match *method {
+            Method::GET => &self.get,
+            Method::POST => &self.post,
+            Method::PUT => &self.put,
+            Method::PATCH => &self.patch,
+            Method::DELETE => &self.delete,
+            _ => return None,
+        };
+
+        router.at(path).ok().map(|matched| *matched.value)
     }
 }
 
 #[derive(Clone)]
 pub struct AuthUser(pub String);
 
-#[derive(Debug, Clone)]
+/// Role of the authenticated user. Requests made with an api key always act with
+/// the plain user role.
+#[derive(Clone)]
+pub struct AuthRole(pub UserRole);
+
+/// Id of the session the access token was issued for.
+#[derive(Clone)]
+pub struct AuthSession(pub String);
+
+/// Expiry of the access token, for requests outliving it such as streams.
+#[derive(Clone)]
+pub struct AuthTokenExpiry(pub DateTime<Utc>);
+
+enum Authorization {
+    Session(jwt::Claims, UserRole),
+    ApiKey(ApiKey),
+}
+
+#[derive(Clone)]
 pub struct AuthLayer {
-    excluded_paths: ExcludedPaths,
+    app_state: SharedAppState,
+    policies: RoutePolicies,
 }
 
 impl AuthLayer {
-    pub fn new() -> Self {
+    pub fn new(app_state: SharedAppState) -> Self {
         Self {
-            excluded_paths: ExcludedPaths::default(),
+            app_state,
+            policies: RoutePolicies::default(),
         }
     }
 
-    pub fn except(mut self, excluded_paths: ExcludedPaths) -> Self {
-        self.excluded_paths = excluded_paths;
+    pub fn policies(mut self, policies: RoutePolicies) -> Self {
+        self.policies = policies;
         self
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct AuthLayerService<S> {
     inner: S,
-    excluded_paths: ExcludedPaths,
+    app_state: SharedAppState,
+    policies: RoutePolicies,
 }
 
 impl<S, ReqBody> Service<Request<ReqBody>> for AuthLayerService<S>
@@ -100,36 +168,87 @@ where
         let method = req.method();
         let path = req.uri().path();
 
-        let excluded = match method {
-            &Method::GET => self.excluded_paths.get.at(path).is_ok(),
-            &Method::POST => self.excluded_paths.post.at(path).is_ok(),
-            &Method::PUT => self.excluded_paths.put.at(path).is_ok(),
-            &Method::PATCH => self.excluded_paths.patch.at(path).is_ok(),
-            &Method::DELETE => self.excluded_paths.delete.at(path).is_ok(),
-            _ => false,
-        };
+        // Requests that don't match any route still require authentication, so
+        // nothing becomes public by accident
+        let policy = self
+            .policies
+            .at(method, path)
+            .unwrap_or(AuthPolicy::authenticated());
+
+        let excluded = policy.public;
+        let required_scope = policy.api_key_scope;
+
+        let app_state = self.app_state.clone();
 
         Box::pin(async move {
+            let headers = req.headers().clone();
+
             if excluded {
+                // Public routes still identify the viewer when a valid token is sent,
+                // anything else is treated as an anonymous request
+                if headers.contains_key(AUTHORIZATION)
+                    && let Ok(authorization) = authorize_user(&app_state, headers).await
+                {
+                    let _ = attach_authorization(&mut req, authorization, required_scope);
+                }
+
                 return inner.call(req).await;
             }
 
-            let headers = req.headers().clone();
+            let authorization = authorize_user(&app_state, headers).await;
 
-            let user_id = authorize_user(headers).await;
+            if let Err(e) = authorization {
+                return Ok(e.into_response());
+            }
 
-            if let Err(e) = user_id {
-                return Ok(HttpError::unauthorized(e).into_response());
+            if let Err(e) = attach_authorization(&mut req, authorization.unwrap(), required_scope) {
+                return Ok(e.into_response());
             }
 
-            req.extensions_mut()
-                .insert::<AuthUser>(AuthUser(user_id.unwrap()));
+            inner.call(req).await
+        })
+    }
+}
 
-            let fut = inner.call(req).await;
+fn attach_authorization<ReqBody>(
+    req: &mut Request<ReqBody>,
+    authorization: Authorization,
+    required_scope: Option<ApiKeyScope>,
+) -> Result<(), HttpError> {
+    match authorization {
+        Authorization::Session(claims, role) => {
+            req.extensions_mut()
+                .insert::<AuthTokenExpiry>(AuthTokenExpiry(claims.expires_at()));
+            req.extensions_mut()
+                .insert::<AuthUser>(AuthUser(claims.sub));
+            req.extensions_mut()
+                .insert::<AuthSession>(AuthSession(claims.sid));
+            req.extensions_mut().insert::<AuthRole>(AuthRole(role));
+        }
+        Authorization::ApiKey(api_key) => {
+            match required_scope {
+                Some(scope) if api_key.has_scope(scope) => (),
+                Some(scope) => {
+                    return Err(HttpError::forbidden(format!(
+                        "Api key is missing the `{}` scope",
+                        scope.to_str()
+                    )));
+                }
+                None => {
+                    return Err(HttpError::forbidden(
+                        "This endpoint can't be accessed with an api key".to_string(),
+                    ));
+                }
+            }
 
-            fut
-        })
+            req.extensions_mut()
+                .insert::<AuthUser>(AuthUser(api_key.user_id));
+            req.extensions_mut()
+                .insert::<AuthRole>(AuthRole(UserRole::User));
+        }
     }
+
+    Ok(())
 }
 
 impl<S> Layer<S> for AuthLayer {
@@ -138,24 +257,72 @@ impl<S> Layer<S> for AuthLayer {
     fn layer(&self, inner: S) -> Self::Service {
         AuthLayerService {
             inner,
-            excluded_paths: self.excluded_paths.clone(),
+            app_state: self.app_state.clone(),
+            policies: self.policies.clone(),
         }
     }
 }
 
-async fn authorize_user(headers: HeaderMap<HeaderValue>) -> Result<String, String> {
+/// Resolves the token in the `Authorization` header to the user it belongs to.
+///
+/// Users that are suspended or banned are rejected here, so no route can be
+/// reached with a token issued before the restriction.
+async fn authorize_user(
+    app_state: &SharedAppState,
+    headers: HeaderMap<HeaderValue>,
+) -> Result<Authorization, HttpError> {
     let token = headers
-        .get("Authorization")
+        .get(AUTHORIZATION)
         .and_then(|header| header.to_str().ok())
         .and_then(|value| value.split(" ").nth(1));
 
     if token.is_none() {
-        return Err("Unauthorized".to_string());
+        return Err(HttpError::unauthorized("Unauthorized".to_string()));
     }
 
     let token = token.unwrap();
 
-    let user_id = jwt::validate_token(token, &CONFIG.jwt_secret).await?;
+    if token.starts_with(API_KEY_PREFIX) {
+        let api_key =
+            service::api_key::touch_active_api_key(&app_state.db, &token::hash_token(token))
+                .await
+                .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+        let api_key = match api_key {
+            Some(api_key) => api_key,
+            None => {
+                return Err(HttpError::unauthorized(
+                    "Invalid or expired api key".to_string(),
+                ));
+            }
+        };
+
+        let account_state = service::user::get_account_state(&app_state.db, &api_key.user_id)
+            .await
+            .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+        if let Some(restriction) = account_state.and_then(|state| state.restriction()) {
+            return Err(HttpError::forbidden(restriction));
+        }
 
-    Ok(user_id)
+        return Ok(Authorization::ApiKey(api_key));
+    }
+
+    let claims = jwt::validate_token(&app_state.jwt_keys, token)
+        .await
+        .map_err(HttpError::unauthorized)?;
+
+    let session_owner = service::session::touch_active_session(&app_state.db, &claims.sid)
+        .await
+        .map_err(|e| HttpError::server_error(e.to_string()))?;
+
+    match session_owner {
+        Some(owner) if owner.id == claims.sub => match owner.account_state.restriction() {
+            Some(restriction) => Err(HttpError::forbidden(restriction)),
+            None => Ok(Authorization::Session(claims, owner.role)),
+        },
+        _ => Err(HttpError::unauthorized(
+            "Session has been revoked".to_string(),
+        )),
+    }
 }
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use sqlx::PgPool;
+
+use crate::{constants::ACCOUNT_PURGE_INTERVAL, core::services::storage::StorageProvider, service};
+
+const PURGE_BATCH_SIZE: i64 = 50;
+
+/// Periodically purges accounts whose deletion grace period has passed, removing
+/// the files they uploaded from storage unless another post, message or profile
+/// still uses them.
+pub fn spawn_account_purge(db: PgPool, storage_provider: Arc<StorageProvider>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ACCOUNT_PURGE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = purge_due_accounts(&db, &storage_provider).await {
+                tracing::error!("Failed to purge deleted accounts: {}", e);
+            }
+        }
+    });
+}
+
+async fn purge_due_accounts(
+    db: &PgPool,
+    storage_provider: &StorageProvider,
+) -> Result<(), sqlx::Error> {
+    let user_ids = service::account::get_accounts_due_for_purge(db, PURGE_BATCH_SIZE).await?;
+
+    for user_id in user_ids {
+        service::account::purge_account(db, &user_id).await?;
+
+        let unused_urls = service::upload::get_unused_uploads(db, &user_id).await?;
+
+        let mut deleted_urls = Vec::with_capacity(unused_urls.len());
+
+        for url in unused_urls {
+            match storage_provider.storage.delete_file(&url).await {
+                Ok(_) => deleted_urls.push(url),
+                Err(e) => {
+                    tracing::warn!("Failed to delete {} of purged user {}: {}", url, user_id, e)
+                }
+            }
+        }
+
+        service::upload::delete_uploads(db, &deleted_urls).await?;
+
+        tracing::info!("Purged account {}", user_id);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use std::{collections::HashSet, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, postgres::PgListener};
+use tokio::sync::broadcast;
+
+use crate::{
+    constants::{STREAM_EVENTS_CAPACITY, STREAM_EVENTS_CHANNEL, STREAM_LISTENER_RETRY_DELAY},
+    models::NotificationKind,
+};
+
+/// Event pushed to the clients of the real-time stream. Events only carry ids and
+/// counters, clients fetch what they need through the api.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum StreamEvent {
+    /// A notification of `user_id` was created or a new actor joined it.
+    Notification {
+        user_id: String,
+        notification_id: String,
+        kind: NotificationKind,
+    },
+    Comment {
+        post_id: String,
+        comment_id: String,
+        parent_id: Option<String>,
+        user_id: String,
+    },
+    Likes {
+        post_id: String,
+        likes_count: i64,
+    },
+    /// A direct message was sent, edited or deleted by `user_id`.
+    Message {
+        conversation_id: String,
+        message_id: String,
+        user_id: String,
+        action: MessageAction,
+        #[serde(skip_serializing)]
+        participant_ids: Vec<String>,
+    },
+    /// `user_id` read every message of the conversation sent up to `read_at`.
+    ReadReceipt {
+        conversation_id: String,
+        user_id: String,
+        read_at: DateTime<Utc>,
+        #[serde(skip_serializing)]
+        participant_ids: Vec<String>,
+    },
+    /// The session was revoked, or deleted along with its account.
+    SessionRevoked {
+        session_id: String,
+    },
+    /// `user_id` was suspended or banned.
+    AccountRestricted {
+        user_id: String,
+    },
+}
+
+/// Why the server ended a stream, sent as the data of its last `closed` event.
+#[derive(Debug, Clone, Copy)]
+pub enum StreamClose {
+    TokenExpired,
+    SessionRevoked,
+    AccountRestricted,
+}
+
+impl StreamClose {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            StreamClose::TokenExpired => "tokenExpired",
+            StreamClose::SessionRevoked => "sessionRevoked",
+            StreamClose::AccountRestricted => "accountRestricted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl StreamEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamEvent::Notification { .. } => "notification",
+            StreamEvent::Comment { .. } => "comment",
+            StreamEvent::Likes { .. } => "likes",
+            StreamEvent::Message { .. } => "message",
+            StreamEvent::ReadReceipt { .. } => "readReceipt",
+            StreamEvent::SessionRevoked { .. } => "sessionRevoked",
+            StreamEvent::AccountRestricted { .. } => "accountRestricted",
+        }
+    }
+
+    /// Whether a client of `user_id` watching `watched_posts` receives the event.
+    pub fn is_for(&self, user_id: &str, watched_posts: &HashSet<String>) -> bool {
+        match self {
+            StreamEvent::Notification {
+                user_id: recipient_id,
+                ..
+            } => recipient_id == user_id,
+            StreamEvent::Comment { post_id, .. } | StreamEvent::Likes { post_id, .. } => {
+                watched_posts.contains(post_id)
+            }
+            StreamEvent::Message {
+                participant_ids, ..
+            }
+            | StreamEvent::ReadReceipt {
+                participant_ids, ..
+            } => participant_ids.iter().any(|id| id == user_id),
+            StreamEvent::SessionRevoked { .. } | StreamEvent::AccountRestricted { .. } => false,
+        }
+    }
+
+    /// Whether the event ends a stream opened by `user_id` with `session_id`, and why.
+    pub fn closes(&self, user_id: &str, session_id: &str) -> Option<StreamClose> {
+        match self {
+            StreamEvent::SessionRevoked {
+                session_id: revoked_id,
+            } if revoked_id == session_id => Some(StreamClose::SessionRevoked),
+            StreamEvent::AccountRestricted {
+                user_id: restricted_id,
+            } if restricted_id == user_id => Some(StreamClose::AccountRestricted),
+            _ => None,
+        }
+    }
+}
+
+/// In-process fan-out of stream events to every connected client.
+///
+/// Each client gets a bounded buffer of [`STREAM_EVENTS_CAPACITY`] events. Publishing
+/// never waits on clients, one that falls behind skips the oldest events and is told
+/// how many it missed so it can refetch.
+///
+/// Events are produced by database triggers and delivered through Postgres
+/// `LISTEN/NOTIFY`, see [`EventHub::spawn_listener`], so every server instance sees
+/// the events of all the others.
+#[derive(Clone)]
+pub struct EventHub {
+    sender: broadcast::Sender<Arc<StreamEvent>>,
+}
+
+impl EventHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(STREAM_EVENTS_CAPACITY);
+
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<StreamEvent>> {
+        self.sender.subscribe()
+    }
+
+    pub fn publish(&self, event: StreamEvent) {
+        // Only fails when no client is connected
+        let _ = self.sender.send(Arc::new(event));
+    }
+
+    /// Relays the events published to [`STREAM_EVENTS_CHANNEL`] to the local clients,
+    /// listening again after losing the connection.
+    pub fn spawn_listener(&self, db: PgPool) {
+        let hub = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = hub.listen(&db).await {
+                    tracing::error!("Stream events listener failed: {}", e);
+                }
+
+                tokio::time::sleep(STREAM_LISTENER_RETRY_DELAY).await;
+            }
+        });
+    }
+
+    async fn listen(&self, db: &PgPool) -> Result<(), sqlx::Error> {
+        let mut listener = PgListener::connect_with(db).await?;
+
+        listener.listen(STREAM_EVENTS_CHANNEL).await?;
+
+        loop {
+            let notification = listener.recv().await?;
+
+            match serde_json::from_str(notification.payload()) {
+                Ok(event) => self.publish(event),
+                Err(e) => tracing::warn!("Ignoring malformed stream event: {}", e),
+            }
+        }
+    }
+}
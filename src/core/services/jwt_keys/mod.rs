@@ -0,0 +1,445 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::Utc;
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+    },
+};
+use rand::Rng;
+use ring::{
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, KeyPair},
+};
+use serde::{Serialize, de::DeserializeOwned};
+use simple_asn1::{ASN1Block, oid};
+
+use crate::config::CONFIG;
+
+const PRIVATE_KEY_EXTENSION: &str = ".pem";
+const PUBLIC_KEY_EXTENSION: &str = ".pub.pem";
+
+const KEYS_RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long consumers may cache the published key set.
+pub const JWKS_MAX_AGE: Duration = Duration::from_secs(300);
+
+// A new key only signs once every instance reloaded it and cached key sets expired
+const KEY_PUBLISH_DELAY: Duration = if JWKS_MAX_AGE.as_secs() > KEYS_RELOAD_INTERVAL.as_secs() {
+    JWKS_MAX_AGE
+} else {
+    KEYS_RELOAD_INTERVAL
+};
+
+// DER prefix of an Ed25519 SubjectPublicKeyInfo, followed by the 32 byte public key
+const ED25519_SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+#[derive(Debug)]
+pub enum JwtKeyError {
+    Io(std::io::Error),
+    InvalidKey(String),
+    NoSigningKey,
+}
+
+impl std::error::Error for JwtKeyError {}
+
+impl fmt::Display for JwtKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JwtKeyError::Io(e) => write!(f, "[Jwt Key Error] Io: {}", e),
+            JwtKeyError::InvalidKey(e) => write!(f, "[Jwt Key Error] Invalid Key: {}", e),
+            JwtKeyError::NoSigningKey => write!(f, "[Jwt Key Error] No signing key available"),
+        }
+    }
+}
+
+struct SigningKey {
+    kid: String,
+    algorithm: Algorithm,
+    encoding_key: EncodingKey,
+    created_at: SystemTime,
+}
+
+struct VerificationKey {
+    algorithm: Algorithm,
+    decoding_key: DecodingKey,
+    jwk: Jwk,
+    created_at: SystemTime,
+}
+
+struct JwtKeys {
+    signing: SigningKey,
+    verification: HashMap<String, VerificationKey>,
+    // A newer key is published but doesn't sign yet
+    has_pending_key: bool,
+}
+
+/// Asymmetric keys used to sign and verify access tokens.
+///
+/// Keys live in `JWT_KEYS_DIR` as pairs of `<kid>.pem` (PKCS#8 private key) and
+/// `<kid>.pub.pem` (SPKI public key), either Ed25519 (EdDSA) or RSA (RS256).
+/// The most recently created private key that has been published for at least
+/// `KEY_PUBLISH_DELAY` signs new tokens, every public key verifies them. Dropping
+/// only the `.pub.pem` of a key keeps it verify-only.
+///
+/// Once the signing key is older than `JWT_KEY_ROTATION_INTERVAL` a new Ed25519
+/// key is generated. It is published right away but only takes over signing after
+/// the delay, so peers and JWKS consumers know it before any token uses it.
+/// Retired public keys are kept until every token they signed has expired.
+/// Instances sharing the directory pick up each other's keys on the next reload.
+pub struct JwtKeyStore {
+    dir: PathBuf,
+    keys: RwLock<JwtKeys>,
+}
+
+impl JwtKeyStore {
+    pub fn new() -> Self {
+        let dir = PathBuf::from(&CONFIG.jwt_keys_dir);
+
+        if !std::fs::exists(&dir).unwrap_or_default() {
+            std::fs::create_dir_all(&dir).expect("Failed to create jwt keys directory");
+        }
+
+        let keys = match Self::load(&dir) {
+            Ok(keys) => keys,
+            Err(JwtKeyError::NoSigningKey) => {
+                tracing::info!("No jwt signing key found, generating one");
+
+                Self::generate_key(&dir).expect("Failed to generate jwt signing key");
+
+                Self::load(&dir).expect("Failed to load jwt keys")
+            }
+            Err(e) => panic!("Failed to load jwt keys: {}", e),
+        };
+
+        Self {
+            dir,
+            keys: RwLock::new(keys),
+        }
+    }
+
+    pub fn encode<T: Serialize>(&self, claims: &T) -> Result<String, String> {
+        let keys = self.keys.read().map_err(|e| e.to_string())?;
+
+        let mut header = Header::new(keys.signing.algorithm);
+        header.kid = Some(keys.signing.kid.clone());
+
+        jsonwebtoken::encode(&header, claims, &keys.signing.encoding_key).map_err(|e| e.to_string())
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, token: &str) -> Result<T, String> {
+        let header = jsonwebtoken::decode_header(token).map_err(|e| e.to_string())?;
+
+        let kid = header.kid.ok_or("Token has no key id".to_string())?;
+
+        let keys = self.keys.read().map_err(|e| e.to_string())?;
+
+        let key = keys
+            .verification
+            .get(&kid)
+            .ok_or("Token was signed with an unknown key".to_string())?;
+
+        let validation = Validation::new(key.algorithm);
+
+        let data = jsonwebtoken::decode::<T>(token, &key.decoding_key, &validation)
+            .map_err(|e| e.to_string())?;
+
+        Ok(data.claims)
+    }
+
+    pub fn jwks(&self) -> JwkSet {
+        let keys = match self.keys.read() {
+            Ok(keys) => keys,
+            Err(e) => e.into_inner(),
+        };
+
+        JwkSet {
+            keys: keys.verification.values().map(|k| k.jwk.clone()).collect(),
+        }
+    }
+
+    /// Periodically reloads keys from disk and rotates the signing key when it is due.
+    pub fn spawn_rotation(self: Arc<Self>) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(KEYS_RELOAD_INTERVAL);
+
+            loop {
+                interval.tick().await;
+
+                let store = self.clone();
+
+                let result = tokio::task::spawn_blocking(move || store.rotate_if_due()).await;
+
+                match result {
+                    Ok(Err(e)) => tracing::error!("Failed to rotate jwt keys: {}", e),
+                    Err(e) => tracing::error!("Jwt keys rotation task failed: {}", e),
+                    Ok(Ok(())) => (),
+                }
+            }
+        });
+    }
+
+    fn rotate_if_due(&self) -> Result<(), JwtKeyError> {
+        let keys = Self::load(&self.dir)?;
+
+        self.prune_retired_keys(&keys)?;
+
+        let is_due = !keys.has_pending_key
+            && age(keys.signing.created_at) >= CONFIG.jwt_key_rotation_interval;
+
+        self.replace_keys(keys);
+
+        if !is_due {
+            return Ok(());
+        }
+
+        let kid = Self::generate_key(&self.dir)?;
+
+        tracing::info!("Published new jwt signing key {}", kid);
+
+        self.replace_keys(Self::load(&self.dir)?);
+
+        Ok(())
+    }
+
+    fn replace_keys(&self, keys: JwtKeys) {
+        match self.keys.write() {
+            Ok(mut guard) => *guard = keys,
+            Err(e) => *e.into_inner() = keys,
+        }
+    }
+
+    /// Removes private keys older than the signing key, they will never sign again,
+    /// and public keys that can't have any unexpired tokens left. Other instances
+    /// may prune the same keys at the same time.
+    fn prune_retired_keys(&self, keys: &JwtKeys) -> Result<(), JwtKeyError> {
+        let retention =
+            CONFIG.jwt_key_rotation_interval + KEY_PUBLISH_DELAY + CONFIG.jwt_expiration_duration;
+
+        for (kid, key) in keys.verification.iter() {
+            if key.created_at >= keys.signing.created_at {
+                continue;
+            }
+
+            let private_key_path = self.dir.join(format!("{}{}", kid, PRIVATE_KEY_EXTENSION));
+
+            remove_if_exists(&private_key_path)?;
+
+            if age(key.created_at) > retention {
+                let public_key_path = self.dir.join(format!("{}{}", kid, PUBLIC_KEY_EXTENSION));
+
+                remove_if_exists(&public_key_path)?;
+
+                tracing::info!("Removed retired jwt key {}", kid);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load(dir: &Path) -> Result<JwtKeys, JwtKeyError> {
+        let mut verification = HashMap::new();
+        let mut private_keys = Vec::new();
+
+        for entry in std::fs::read_dir(dir).map_err(JwtKeyError::Io)? {
+            let entry = entry.map_err(JwtKeyError::Io)?;
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let created_at = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .map_err(JwtKeyError::Io)?;
+
+            if let Some(kid) = file_name.strip_suffix(PUBLIC_KEY_EXTENSION) {
+                let pem = std::fs::read(entry.path()).map_err(JwtKeyError::Io)?;
+                let key = parse_public_key(kid, &pem, created_at)?;
+
+                verification.insert(kid.to_string(), key);
+            } else if let Some(kid) = file_name.strip_suffix(PRIVATE_KEY_EXTENSION) {
+                private_keys.push((kid.to_string(), entry.path(), created_at));
+            }
+        }
+
+        private_keys.retain(|(kid, _, _)| verification.contains_key(kid));
+
+        // Before any key has been published long enough, such as on the first start,
+        // the oldest one signs so instances starting together agree on it
+        let (kid, path, created_at) = private_keys
+            .iter()
+            .filter(|(_, _, created_at)| age(*created_at) >= KEY_PUBLISH_DELAY)
+            .max_by_key(|(_, _, created_at)| *created_at)
+            .or_else(|| {
+                private_keys
+                    .iter()
+                    .min_by_key(|(_, _, created_at)| *created_at)
+            })
+            .cloned()
+            .ok_or(JwtKeyError::NoSigningKey)?;
+
+        let has_pending_key = private_keys
+            .iter()
+            .any(|(_, _, key_created_at)| *key_created_at > created_at);
+
+        let algorithm = verification[&kid].algorithm;
+        let pem = std::fs::read(path).map_err(JwtKeyError::Io)?;
+
+        let encoding_key = match algorithm {
+            Algorithm::EdDSA => EncodingKey::from_ed_pem(&pem),
+            _ => EncodingKey::from_rsa_pem(&pem),
+        }
+        .map_err(|e| JwtKeyError::InvalidKey(format!("{}: {}", kid, e)))?;
+
+        Ok(JwtKeys {
+            signing: SigningKey {
+                kid,
+                algorithm,
+                encoding_key,
+                created_at,
+            },
+            verification,
+            has_pending_key,
+        })
+    }
+
+    fn generate_key(dir: &Path) -> Result<String, JwtKeyError> {
+        // Instances rotating in the same second each get their own key files
+        let kid = format!(
+            "key_{}_{:08x}",
+            Utc::now().format("%Y%m%d%H%M%S"),
+            rand::rng().random::<u32>()
+        );
+
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|e| JwtKeyError::InvalidKey(e.to_string()))?;
+
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref())
+            .map_err(|e| JwtKeyError::InvalidKey(e.to_string()))?;
+
+        let public_key = [&ED25519_SPKI_PREFIX[..], key_pair.public_key().as_ref()].concat();
+
+        let private_pem = pem::encode(&pem::Pem::new("PRIVATE KEY", pkcs8.as_ref()));
+        let public_pem = pem::encode(&pem::Pem::new("PUBLIC KEY", public_key));
+
+        let private_key_path = dir.join(format!("{}{}", kid, PRIVATE_KEY_EXTENSION));
+
+        std::fs::write(&private_key_path, private_pem).map_err(JwtKeyError::Io)?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            std::fs::set_permissions(&private_key_path, std::fs::Permissions::from_mode(0o600))
+                .map_err(JwtKeyError::Io)?;
+        }
+
+        std::fs::write(
+            dir.join(format!("{}{}", kid, PUBLIC_KEY_EXTENSION)),
+            public_pem,
+        )
+        .map_err(JwtKeyError::Io)?;
+
+        Ok(kid)
+    }
+}
+
+fn age(created_at: SystemTime) -> Duration {
+    SystemTime::now()
+        .duration_since(created_at)
+        .unwrap_or_default()
+}
+
+fn remove_if_exists(path: &Path) -> Result<(), JwtKeyError> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(JwtKeyError::Io(e)),
+        _ => Ok(()),
+    }
+}
+
+fn parse_public_key(
+    kid: &str,
+    pem: &[u8],
+    created_at: SystemTime,
+) -> Result<VerificationKey, JwtKeyError> {
+    let invalid_key = |message: &str| JwtKeyError::InvalidKey(format!("{}: {}", kid, message));
+
+    let pem = pem::parse(pem).map_err(|e| invalid_key(&e.to_string()))?;
+    let blocks = simple_asn1::from_der(pem.contents()).map_err(|e| invalid_key(&e.to_string()))?;
+
+    // SubjectPublicKeyInfo ::= SEQUENCE { algorithm AlgorithmIdentifier, subjectPublicKey BIT STRING }
+    let (key_oid, key_bytes) = match blocks.first() {
+        Some(ASN1Block::Sequence(_, spki)) => match spki.as_slice() {
+            [
+                ASN1Block::Sequence(_, algorithm),
+                ASN1Block::BitString(_, _, bytes),
+            ] => match algorithm.first() {
+                Some(ASN1Block::ObjectIdentifier(_, key_oid)) => (key_oid.clone(), bytes),
+                _ => return Err(invalid_key("missing key algorithm")),
+            },
+            _ => return Err(invalid_key("malformed public key")),
+        },
+        _ => return Err(invalid_key("malformed public key")),
+    };
+
+    let (algorithm, key_algorithm, parameters) = if key_oid == oid!(1, 3, 101, 112) {
+        let parameters = AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            key_type: OctetKeyPairType::OctetKeyPair,
+            curve: EllipticCurve::Ed25519,
+            x: URL_SAFE_NO_PAD.encode(key_bytes),
+        });
+
+        (Algorithm::EdDSA, KeyAlgorithm::EdDSA, parameters)
+    } else if key_oid == oid!(1, 2, 840, 113549, 1, 1, 1) {
+        // RSAPublicKey ::= SEQUENCE { modulus INTEGER, publicExponent INTEGER }
+        let rsa_blocks =
+            simple_asn1::from_der(key_bytes).map_err(|e| invalid_key(&e.to_string()))?;
+
+        let (n, e) = match rsa_blocks.first() {
+            Some(ASN1Block::Sequence(_, values)) => match values.as_slice() {
+                [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)] => {
+                    (n.to_bytes_be().1, e.to_bytes_be().1)
+                }
+                _ => return Err(invalid_key("malformed rsa public key")),
+            },
+            _ => return Err(invalid_key("malformed rsa public key")),
+        };
+
+        let parameters = AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: URL_SAFE_NO_PAD.encode(n),
+            e: URL_SAFE_NO_PAD.encode(e),
+        });
+
+        (Algorithm::RS256, KeyAlgorithm::RS256, parameters)
+    } else {
+        return Err(invalid_key("unsupported key type, expected Ed25519 or RSA"));
+    };
+
+    let jwk = Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(key_algorithm),
+            key_id: Some(kid.to_string()),
+            ..Default::default()
+        },
+        algorithm: parameters,
+    };
+
+    let decoding_key = DecodingKey::from_jwk(&jwk).map_err(|e| invalid_key(&e.to_string()))?;
+
+    Ok(VerificationKey {
+        algorithm,
+        decoding_key,
+        jwk,
+        created_at,
+    })
+}
@@ -0,0 +1,86 @@
+//! Headers lettre doesn't provide.
+
+use lettre::message::header::{Header, HeaderName, HeaderValue};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// `List-Unsubscribe` (RFC 2369), the URL mail clients offer to unsubscribe with.
+#[derive(Debug, Clone)]
+pub struct ListUnsubscribe(pub String);
+
+impl Header for ListUnsubscribe {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe")
+    }
+
+    fn parse(s: &str) -> Result<Self, BoxError> {
+        let url = s
+            .trim()
+            .strip_prefix('<')
+            .and_then(|s| s.strip_suffix('>'))
+            .ok_or("List-Unsubscribe must be an url between angle brackets")?;
+
+        Ok(Self(url.to_string()))
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), format!("<{}>", self.0))
+    }
+}
+
+/// `List-Unsubscribe-Post` (RFC 8058), tells mail clients they can unsubscribe with
+/// a POST to the `List-Unsubscribe` URL, without opening it.
+#[derive(Debug, Clone)]
+pub struct ListUnsubscribePost;
+
+impl Header for ListUnsubscribePost {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("List-Unsubscribe-Post")
+    }
+
+    fn parse(_: &str) -> Result<Self, BoxError> {
+        Ok(Self)
+    }
+
+    fn display(&self) -> HeaderValue {
+        HeaderValue::new(Self::name(), "List-Unsubscribe=One-Click".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lettre::Message;
+
+    use super::*;
+
+    #[test]
+    fn advertises_one_click_unsubscribe() {
+        let url = format!(
+            "https://api.example.com/notifications/unsubscribe?token={}.{}",
+            "a".repeat(60),
+            "b".repeat(43)
+        );
+
+        let message = Message::builder()
+            .from("from@example.com".parse().unwrap())
+            .to("to@example.com".parse().unwrap())
+            .subject("Subject")
+            .header(ListUnsubscribe(url.clone()))
+            .header(ListUnsubscribePost)
+            .body("Body".to_string())
+            .unwrap();
+
+        let headers = message.headers();
+
+        assert_eq!(headers.get::<ListUnsubscribe>().unwrap().0, url);
+        assert_eq!(
+            headers.get_raw("List-Unsubscribe-Post"),
+            Some("List-Unsubscribe=One-Click")
+        );
+        assert!(
+            String::from_utf8(message.formatted())
+                .unwrap()
+                .contains(&format!("List-Unsubscribe: <{url}>"))
+        );
+    }
+}
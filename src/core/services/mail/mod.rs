@@ -1,3 +1,5 @@
+mod headers;
+
 use lettre::{
     SmtpTransport, Transport,
     address::AddressError,
@@ -5,7 +7,12 @@ use lettre::{
     transport::smtp::authentication::Credentials,
 };
 
-use crate::config::CONFIG;
+use crate::{
+    config::CONFIG,
+    models::{DigestActivity, DigestFrequency},
+};
+
+use headers::{ListUnsubscribe, ListUnsubscribePost};
 
 pub struct MailService {
     smtp: SmtpTransport,
@@ -32,7 +39,133 @@ impl MailService {
     }
 
     pub async fn send_verification_mail(&self, email: String, code: String) -> Result<(), String> {
-        let m = Message::builder()
+        self.send_text_mail(
+            email,
+            "Verification Code",
+            format!("Your verification code is {}", code),
+            None,
+        )
+        .await
+    }
+
+    /// Sends the pin confirming an email change to the new address.
+    pub async fn send_email_change_mail(
+        &self,
+        new_email: String,
+        code: String,
+    ) -> Result<(), String> {
+        self.send_text_mail(
+            new_email,
+            "Confirm your new email address",
+            format!(
+                "Your code to confirm this email address is {}. If you didn't request this change, you can ignore this email.",
+                code
+            ),
+            None,
+        )
+        .await
+    }
+
+    /// Lets the current address know a change was requested, so a hijacked
+    /// session can't move the account away silently.
+    pub async fn send_email_change_requested_mail(
+        &self,
+        email: String,
+        new_email: &str,
+    ) -> Result<(), String> {
+        self.send_text_mail(
+            email,
+            "Email change requested",
+            format!(
+                "A request was made to change your account's email address to {}. If this wasn't you, sign out of all sessions and contact support.",
+                new_email
+            ),
+            None,
+        )
+        .await
+    }
+
+    pub async fn send_email_changed_mail(
+        &self,
+        email: String,
+        new_email: &str,
+    ) -> Result<(), String> {
+        self.send_text_mail(
+            email,
+            "Your email address was changed",
+            format!(
+                "Your account's email address was changed to {}. If this wasn't you, contact support.",
+                new_email
+            ),
+            None,
+        )
+        .await
+    }
+
+    pub async fn send_notification_mail(
+        &self,
+        email: String,
+        summary: &str,
+        unsubscribe_url: &str,
+    ) -> Result<(), String> {
+        self.send_text_mail(
+            email,
+            summary,
+            format!(
+                "{}.\n\nTo stop getting these emails, visit {}",
+                summary, unsubscribe_url
+            ),
+            Some(unsubscribe_url),
+        )
+        .await
+    }
+
+    pub async fn send_digest_mail(
+        &self,
+        email: String,
+        frequency: DigestFrequency,
+        activity: &DigestActivity,
+        unsubscribe_url: &str,
+    ) -> Result<(), String> {
+        let period = match frequency {
+            DigestFrequency::Weekly => "week",
+            _ => "day",
+        };
+
+        let mut body = format!("Here's what happened on your posts this {}:\n\n", period);
+
+        for post in &activity.posts {
+            body.push_str(&format!(
+                "- {}: {} new likes, {} new comments\n",
+                post.title, post.likes_count, post.comments_count
+            ));
+        }
+
+        if activity.followers_count > 0 {
+            body.push_str(&format!(
+                "\nYou have {} new followers.\n",
+                activity.followers_count
+            ));
+        }
+
+        body.push_str(&format!(
+            "\nTo stop getting this digest, visit {}",
+            unsubscribe_url
+        ));
+
+        self.send_text_mail(email, "Your activity digest", body, Some(unsubscribe_url))
+            .await
+    }
+
+    /// Mails sent with an `unsubscribe_url` advertise it for one-click unsubscribe.
+    async fn send_text_mail(
+        &self,
+        to: String,
+        subject: &str,
+        body: String,
+        unsubscribe_url: Option<&str>,
+    ) -> Result<(), String> {
+        let mut builder = Message::builder()
             .from(
                 CONFIG
                     .mail_config
@@ -40,11 +173,17 @@ impl MailService {
                     .parse()
                     .map_err(|e: AddressError| e.to_string())?,
             )
-            .to(email.parse().map_err(|e: AddressError| e.to_string())?)
-            .subject("Verification Code")
-            .header(ContentType::TEXT_PLAIN)
-            .body(format!("Your verification code is {}", code))
-            .map_err(|e| e.to_string())?;
+            .to(to.parse().map_err(|e: AddressError| e.to_string())?)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN);
+
+        if let Some(url) = unsubscribe_url {
+            builder = builder
+                .header(ListUnsubscribe(url.to_string()))
+                .header(ListUnsubscribePost);
+        }
+
+        let m = builder.body(body).map_err(|e| e.to_string())?;
 
         let mailer = self.smtp.clone();
 
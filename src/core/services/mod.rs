@@ -1,2 +1,6 @@
+pub mod account_purge;
+pub mod event_hub;
+pub mod jwt_keys;
 pub mod mail;
+pub mod notification_mail;
 pub mod storage;
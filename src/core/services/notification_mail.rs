@@ -0,0 +1,126 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::Utc;
+use sqlx::PgPool;
+
+use crate::{
+    constants::{NOTIFICATION_MAIL_DELAY, NOTIFICATION_MAIL_INTERVAL},
+    core::{
+        services::mail::MailService,
+        utils::unsubscribe::{self, UnsubscribeScope},
+    },
+    service,
+};
+
+const MAIL_BATCH_SIZE: i64 = 100;
+
+/// Periodically emails notifications of users who get them by email, once they
+/// settled, and sends activity digests that are due.
+pub fn spawn_notification_mail(db: PgPool, mail_service: Arc<MailService>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(NOTIFICATION_MAIL_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = send_notification_mails(&db, &mail_service).await {
+                tracing::error!("Failed to send notification emails: {}", e);
+            }
+
+            if let Err(e) = send_due_digests(&db, &mail_service).await {
+                tracing::error!("Failed to send digest emails: {}", e);
+            }
+        }
+    });
+}
+
+async fn send_notification_mails(
+    db: &PgPool,
+    mail_service: &MailService,
+) -> Result<(), sqlx::Error> {
+    let settled_before = Utc::now() - NOTIFICATION_MAIL_DELAY;
+
+    let pending = service::notification::claim_pending_notification_mails(
+        db,
+        settled_before,
+        MAIL_BATCH_SIZE,
+    )
+    .await?;
+
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<String> = pending.iter().map(|p| p.notification_id.clone()).collect();
+
+    let mut notifications: HashMap<String, _> =
+        service::notification::get_notifications_by_ids(db, &ids)
+            .await?
+            .into_iter()
+            .map(|n| (n.notification.id.clone(), n))
+            .collect();
+
+    let mut failed_ids = Vec::new();
+
+    for mail in pending {
+        let Some(notification) = notifications.remove(&mail.notification_id) else {
+            continue;
+        };
+
+        let unsubscribe_url = unsubscribe::unsubscribe_url(
+            &mail.user_id,
+            UnsubscribeScope::Email(notification.notification.kind),
+        );
+
+        if let Err(e) = mail_service
+            .send_notification_mail(mail.email, &notification.summary, &unsubscribe_url)
+            .await
+        {
+            tracing::warn!(
+                "Failed to email notification {}: {}",
+                mail.notification_id,
+                e
+            );
+            failed_ids.push(mail.notification_id);
+        }
+    }
+
+    service::notification::release_notification_mails(db, &failed_ids).await
+}
+
+/// Digests without any activity are skipped but still start a new period.
+async fn send_due_digests(db: &PgPool, mail_service: &MailService) -> Result<(), sqlx::Error> {
+    let due = service::notification_preference::claim_due_digests(db, MAIL_BATCH_SIZE).await?;
+
+    for digest in due {
+        let activity = service::notification_preference::get_digest_activity(
+            db,
+            &digest.user_id,
+            digest.last_sent_at,
+            digest.until,
+        )
+        .await?;
+
+        if activity.posts.is_empty() && activity.followers_count == 0 {
+            continue;
+        }
+
+        let unsubscribe_url =
+            unsubscribe::unsubscribe_url(&digest.user_id, UnsubscribeScope::Digest);
+
+        if let Err(e) = mail_service
+            .send_digest_mail(
+                digest.email.clone(),
+                digest.frequency,
+                &activity,
+                &unsubscribe_url,
+            )
+            .await
+        {
+            tracing::warn!("Failed to send digest to {}: {}", digest.user_id, e);
+            service::notification_preference::release_digest(db, &digest).await?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,7 +1,7 @@
 use std::{
     fs::DirBuilder,
     io::{Cursor, Read},
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
 use crate::{
@@ -30,10 +30,7 @@ impl DiskStorage {
 
         path.push(storage_path);
 
-        let exists = match std::fs::exists(&path) {
-            Ok(v) => v,
-            Err(_) => false,
-        };
+        let exists = std::fs::exists(&path).unwrap_or_default();
 
         match exists {
             true => tracing::info!("Disk storage path already exists at {}", path.display()),
@@ -47,8 +44,19 @@ impl DiskStorage {
         format!("{}/{}", self.path.display(), file_name)
     }
 
+    /// Resolves an url handed out by `upload_file` to the file on disk. Only the file
+    /// name is taken from the url so it can never point outside the storage path.
+    fn get_file_path(&self, url: &str) -> Result<PathBuf, StorageError> {
+        let file_name = url
+            .strip_prefix(constants::SERVER_URL.as_str())
+            .and_then(|path| Path::new(path).file_name())
+            .ok_or_else(|| StorageError::InvalidUrl(url.to_string()))?;
+
+        Ok(self.path.join(file_name))
+    }
+
     fn create_storage_dir(path: &PathBuf) {
-        match DirBuilder::new().create(&path) {
+        match DirBuilder::new().create(path) {
             Ok(_) => tracing::info!("Disk storage path created at {}", path.display()),
             Err(e) => tracing::error!("Failed to create disk storage path: {}", e),
         }
@@ -96,8 +104,26 @@ impl Storage for DiskStorage {
             writer.write(bytes).await.map_err(StorageError::Io)?;
         }
 
-        let url = format!("{}/{}", constants::SERVER_URL.to_string(), full_path);
+        let url = format!("{}/{}", *constants::SERVER_URL, full_path);
 
         Ok(url)
     }
+
+    async fn download_file(&self, url: &str) -> Result<Bytes, StorageError> {
+        let path = self.get_file_path(url)?;
+
+        let data = tokio::fs::read(path).await.map_err(StorageError::Io)?;
+
+        Ok(Bytes::from(data))
+    }
+
+    async fn delete_file(&self, url: &str) -> Result<(), StorageError> {
+        let path = self.get_file_path(url)?;
+
+        match tokio::fs::remove_file(path).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(StorageError::Io(e)),
+        }
+    }
 }
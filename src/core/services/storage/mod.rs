@@ -15,6 +15,7 @@ pub enum StorageError {
     S3(S3Error),
     Io(std::io::Error),
     InvalidUploadOptions(String),
+    InvalidUrl(String),
 }
 
 impl std::error::Error for StorageError {}
@@ -27,6 +28,7 @@ impl fmt::Display for StorageError {
             StorageError::InvalidUploadOptions(e) => {
                 write!(f, "[Storage Error] Invalid Upload Options: {}", e)
             }
+            StorageError::InvalidUrl(e) => write!(f, "[Storage Error] Invalid Url: {}", e),
         }
     }
 }
@@ -64,6 +66,11 @@ pub trait Storage {
         data: Bytes,
         upload_options: UploadOptions,
     ) -> Result<String, StorageError>;
+
+    /// Reads a file previously returned by [`Storage::upload_file`].
+    async fn download_file(&self, url: &str) -> Result<Bytes, StorageError>;
+
+    async fn delete_file(&self, url: &str) -> Result<(), StorageError>;
 }
 
 pub struct StorageProvider {
@@ -31,6 +31,19 @@ impl S3Service {
     }
 }
 
+impl S3Service {
+    fn get_object_path<'a>(&self, url: &'a str) -> Result<&'a str, StorageError> {
+        let base_url = format!(
+            "https://{}.s3.{}.amazonaws.com/",
+            self.bucket.name(),
+            self.bucket.region()
+        );
+
+        url.strip_prefix(&base_url)
+            .ok_or_else(|| StorageError::InvalidUrl(url.to_string()))
+    }
+}
+
 #[async_trait]
 impl Storage for S3Service {
     async fn upload_file(
@@ -53,7 +66,7 @@ impl Storage for S3Service {
         let s3_path = format!(
             "uploads/{}_{}.{}",
             name,
-            Utc::now().timestamp_millis().to_string(),
+            Utc::now().timestamp_millis(),
             extension
         );
 
@@ -71,4 +84,27 @@ impl Storage for S3Service {
 
         Ok(url)
     }
+
+    async fn download_file(&self, url: &str) -> Result<Bytes, StorageError> {
+        let path = self.get_object_path(url)?;
+
+        let response = self
+            .bucket
+            .get_object(path)
+            .await
+            .map_err(StorageError::S3)?;
+
+        Ok(response.bytes().clone())
+    }
+
+    async fn delete_file(&self, url: &str) -> Result<(), StorageError> {
+        let path = self.get_object_path(url)?;
+
+        self.bucket
+            .delete_object(path)
+            .await
+            .map_err(StorageError::S3)?;
+
+        Ok(())
+    }
 }
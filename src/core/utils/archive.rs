@@ -0,0 +1,17 @@
+use std::io::{Cursor, Write};
+
+use zip::{ZipWriter, result::ZipResult, write::SimpleFileOptions};
+
+/// Builds an in-memory zip archive out of `(path, contents)` entries.
+pub fn build_zip(entries: Vec<(String, Vec<u8>)>) -> ZipResult<Vec<u8>> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+
+    let options = SimpleFileOptions::default();
+
+    for (path, contents) in entries {
+        writer.start_file(path, options)?;
+        writer.write_all(&contents)?;
+    }
+
+    Ok(writer.finish()?.into_inner())
+}
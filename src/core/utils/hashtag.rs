@@ -0,0 +1,123 @@
+use crate::constants::{MAX_HASHTAG_LENGTH, MAX_HASHTAGS_PER_POST};
+
+/// Normalized (lowercased) hashtags of `content`, in order of first appearance.
+///
+/// A hashtag is a `#` followed by letters, digits or underscores. It is ignored when
+/// the `#` is glued to a previous word (`issue#12`, urls), when it only has digits
+/// (`#1`) or when it is longer than [`MAX_HASHTAG_LENGTH`].
+pub fn extract_hashtags(content: &str) -> Vec<String> {
+    let mut hashtags: Vec<String> = Vec::new();
+    let mut previous = None;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '#' || previous.is_some_and(is_hashtag_char) {
+            previous = Some(c);
+            continue;
+        }
+
+        let mut hashtag = String::new();
+
+        while let Some(&next) = chars.peek()
+            && is_hashtag_char(next)
+        {
+            hashtag.push(next);
+            chars.next();
+        }
+
+        previous = hashtag.chars().last().or(Some(c));
+
+        // Lowercasing can add characters (`İ` becomes `i̇`), so the length is checked on
+        // the stored form
+        let hashtag = hashtag.to_lowercase();
+
+        let length = hashtag.chars().count();
+
+        if length == 0 || length > MAX_HASHTAG_LENGTH || hashtag.chars().all(|c| c.is_ascii_digit())
+        {
+            continue;
+        }
+
+        if !hashtags.contains(&hashtag) {
+            hashtags.push(hashtag);
+        }
+
+        if hashtags.len() == MAX_HASHTAGS_PER_POST {
+            break;
+        }
+    }
+
+    hashtags
+}
+
+fn is_hashtag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_lowercased_hashtags_in_order() {
+        assert_eq!(
+            extract_hashtags("#Rust and #tokio, then #rust again"),
+            vec!["rust", "tokio"]
+        );
+    }
+
+    #[test]
+    fn ignores_hashtags_glued_to_a_word() {
+        assert_eq!(
+            extract_hashtags("issue#12 https://example.com/page#section a#b #ok"),
+            vec!["ok"]
+        );
+    }
+
+    #[test]
+    fn ignores_digit_only_hashtags() {
+        assert_eq!(
+            extract_hashtags("#1 #2024 #2024_goals #a1"),
+            vec!["2024_goals", "a1"]
+        );
+    }
+
+    #[test]
+    fn stops_at_punctuation() {
+        assert_eq!(
+            extract_hashtags("#rust. #tokio- #serde! #café"),
+            vec!["rust", "tokio", "serde", "café"]
+        );
+    }
+
+    #[test]
+    fn ignores_hashtags_longer_than_the_limit() {
+        let longest = "a".repeat(MAX_HASHTAG_LENGTH);
+        let content = format!("#{longest} #{longest}b");
+
+        assert_eq!(extract_hashtags(&content), vec![longest]);
+    }
+
+    #[test]
+    fn checks_the_length_after_lowercasing() {
+        // `İ` lowercases to two characters
+        let content = format!("#{}", "İ".repeat(MAX_HASHTAG_LENGTH / 2 + 1));
+
+        assert!(extract_hashtags(&content).is_empty());
+    }
+
+    #[test]
+    fn caps_hashtags_per_post() {
+        let content: Vec<String> = (0..MAX_HASHTAGS_PER_POST + 5)
+            .map(|i| format!("#tag{i}"))
+            .collect();
+
+        let hashtags = extract_hashtags(&content.join(" "));
+
+        assert_eq!(hashtags.len(), MAX_HASHTAGS_PER_POST);
+        assert_eq!(
+            hashtags.last().unwrap(),
+            &format!("tag{}", MAX_HASHTAGS_PER_POST - 1)
+        );
+    }
+}
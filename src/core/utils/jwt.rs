@@ -1,47 +1,44 @@
-use chrono::Utc;
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::config::CONFIG;
+use crate::{config::CONFIG, core::services::jwt_keys::JwtKeyStore};
 
 #[derive(Serialize, Deserialize)]
 pub struct Claims {
-    sub: String,
+    pub sub: String,
+    pub sid: String,
     exp: usize,
     iat: usize,
 }
 
 impl Claims {
-    fn new(user_id: &str) -> Self {
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.exp as i64, 0).unwrap_or_default()
+    }
+
+    fn new(user_id: &str, session_id: &str) -> Self {
         let now = Utc::now();
         let exp = now + CONFIG.jwt_expiration_duration;
 
         Self {
             sub: user_id.to_string(),
+            sid: session_id.to_string(),
             exp: exp.timestamp() as usize,
             iat: now.timestamp() as usize,
         }
     }
 }
 
-pub async fn generate_token(user_id: &str, secret: &str) -> Result<String, String> {
-    let claims = Claims::new(user_id);
+pub async fn generate_token(
+    keys: &JwtKeyStore,
+    user_id: &str,
+    session_id: &str,
+) -> Result<String, String> {
+    let claims = Claims::new(user_id, session_id);
 
-    jsonwebtoken::encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    )
-    .map_err(|e| e.to_string())
+    keys.encode(&claims)
 }
 
-pub async fn validate_token(token: &str, secret: &str) -> Result<String, String> {
-    let claims = jsonwebtoken::decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(claims.claims.sub)
+pub async fn validate_token(keys: &JwtKeyStore, token: &str) -> Result<Claims, String> {
+    keys.decode::<Claims>(token)
 }
@@ -0,0 +1,127 @@
+use crate::constants::MAX_MENTIONS_PER_CONTENT;
+
+/// An `@username` in some content, `start` and `end` are the byte offsets of the
+/// whole mention including the `@`.
+pub struct MentionCandidate {
+    pub username: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// `@username` mentions of `content`, in order of appearance.
+///
+/// Usernames are made of letters, digits, `_`, `.` and `-`, trailing dots and dashes
+/// are treated as punctuation. A `@` glued to a previous word (emails) is ignored.
+/// Only the first [`MAX_MENTIONS_PER_CONTENT`] distinct usernames are returned.
+pub fn extract_mentions(content: &str) -> Vec<MentionCandidate> {
+    let mut mentions: Vec<MentionCandidate> = Vec::new();
+    let mut usernames: Vec<&str> = Vec::new();
+    let mut previous = None;
+    let mut chars = content.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c != '@' || previous.is_some_and(is_username_char) {
+            previous = Some(c);
+            continue;
+        }
+
+        let mut end = start + c.len_utf8();
+
+        while let Some(&(index, next)) = chars.peek()
+            && is_username_char(next)
+        {
+            end = index + next.len_utf8();
+            previous = Some(next);
+            chars.next();
+        }
+
+        let username = content[start + 1..end].trim_end_matches(['.', '-']);
+
+        if username.is_empty() {
+            previous = Some(c);
+            continue;
+        }
+
+        if !usernames.contains(&username) {
+            if usernames.len() == MAX_MENTIONS_PER_CONTENT {
+                continue;
+            }
+
+            usernames.push(username);
+        }
+
+        mentions.push(MentionCandidate {
+            username: username.to_string(),
+            start,
+            end: start + 1 + username.len(),
+        });
+    }
+
+    mentions
+}
+
+fn is_username_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usernames(content: &str) -> Vec<String> {
+        extract_mentions(content)
+            .into_iter()
+            .map(|mention| mention.username)
+            .collect()
+    }
+
+    #[test]
+    fn extracts_mentions_in_order() {
+        assert_eq!(
+            usernames("@alice and @bob.smith, @alice again"),
+            vec!["alice", "bob.smith", "alice"]
+        );
+    }
+
+    #[test]
+    fn ignores_mentions_glued_to_a_word() {
+        assert_eq!(
+            usernames("mail alice@example.com or a@b, ask @carol"),
+            vec!["carol"]
+        );
+    }
+
+    #[test]
+    fn treats_trailing_dots_and_dashes_as_punctuation() {
+        assert_eq!(
+            usernames("thanks @alice. and @bob- and @carol-- @dave.-"),
+            vec!["alice", "bob", "carol", "dave"]
+        );
+        assert!(usernames("@ @. @-").is_empty());
+    }
+
+    #[test]
+    fn offsets_are_bytes_around_the_mention() {
+        let content = "héllo @zoë. ok";
+
+        let mentions = extract_mentions(content);
+
+        assert_eq!(mentions.len(), 1);
+        assert_eq!(&content[mentions[0].start..mentions[0].end], "@zoë");
+        assert_eq!(mentions[0].start, "héllo ".len());
+    }
+
+    #[test]
+    fn caps_distinct_usernames_per_content() {
+        let content: Vec<String> = (0..MAX_MENTIONS_PER_CONTENT + 5)
+            .map(|i| format!("@user{i}"))
+            .collect();
+        let content = format!("{} @user0", content.join(" "));
+
+        let usernames = usernames(&content);
+
+        assert_eq!(usernames.len(), MAX_MENTIONS_PER_CONTENT + 1);
+        assert_eq!(usernames.last().unwrap(), "user0");
+        assert!(!usernames.contains(&format!("user{MAX_MENTIONS_PER_CONTENT}")));
+    }
+}
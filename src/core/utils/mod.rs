@@ -1,2 +1,9 @@
+pub mod archive;
+pub mod hashtag;
 pub mod jwt;
+pub mod mention;
+pub mod pagination;
 pub mod pin;
+pub mod token;
+pub mod totp;
+pub mod unsubscribe;
@@ -0,0 +1,224 @@
+use axum::{
+    http::{HeaderValue, Uri, header},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sqlx::{Postgres, QueryBuilder};
+
+use crate::{
+    core::extractors::json::Json,
+    types::{Cursor, Page, Paginated},
+};
+
+/// Appends the ordering and paging of a list sorted by `(created_at, id)` newest
+/// first. Must come right after the `WHERE` clause, `alias` is the listed table.
+///
+/// Fetches `limit + 1` rows, the extra one only telling whether there is a next page.
+pub fn push_page(
+    query_builder: &mut QueryBuilder<'_, Postgres>,
+    alias: &str,
+    page: &Page,
+    limit: i64,
+) {
+    if let Page::After(Some(cursor)) = page {
+        query_builder.push(format!(" AND ({alias}.created_at, {alias}.id) < ("));
+        query_builder.push_bind(cursor.created_at);
+        query_builder.push(", ");
+        query_builder.push_bind(cursor.id.clone());
+        query_builder.push(")");
+    }
+
+    query_builder.push(format!(
+        " ORDER BY {alias}.created_at DESC, {alias}.id DESC"
+    ));
+
+    if let Page::Offset(offset) = page {
+        query_builder.push(" OFFSET ");
+        query_builder.push_bind(*offset);
+    }
+
+    query_builder.push(" LIMIT ");
+    query_builder.push_bind(limit + 1);
+}
+
+/// Wraps a page of `items` in a [`Paginated`] envelope and advertises the next and
+/// previous pages in an RFC 8288 `Link` header, relative to the requested `uri`.
+pub fn paginated<T: Serialize>(
+    uri: &Uri,
+    items: Vec<T>,
+    page: &Page,
+    limit: i64,
+    total: Option<i64>,
+    cursor_of: impl Fn(&T) -> Cursor,
+) -> Response {
+    let paginated = Paginated::new(items, page, limit, total, cursor_of);
+
+    let mut links = Vec::new();
+
+    match (paginated.offset, paginated.next_cursor.as_deref()) {
+        (Some(offset), _) => {
+            if paginated.has_more {
+                links.push(link(
+                    uri,
+                    &format!("offset={}", offset + limit),
+                    limit,
+                    "next",
+                ));
+            }
+
+            if offset > 0 {
+                let prev = (offset - limit).max(0);
+                links.push(link(uri, &format!("offset={prev}"), limit, "prev"));
+            }
+        }
+        // Keyset pages can only be walked forward
+        (None, Some(cursor)) => links.push(link(uri, &format!("cursor={cursor}"), limit, "next")),
+        (None, None) => (),
+    }
+
+    let mut response = Json(paginated).into_response();
+
+    if !links.is_empty()
+        && let Ok(value) = HeaderValue::from_str(&links.join(", "))
+    {
+        response.headers_mut().insert(header::LINK, value);
+    }
+
+    response
+}
+
+/// Link to the same list with its paging parameters replaced by `position` and `limit`,
+/// other query parameters such as filters are kept as they are.
+fn link(uri: &Uri, position: &str, limit: i64, rel: &str) -> String {
+    let mut params: Vec<&str> = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|param| {
+            let key = param.split_once('=').map_or(*param, |(key, _)| key);
+            !param.is_empty() && !matches!(key, "offset" | "limit" | "cursor")
+        })
+        .collect();
+
+    let limit = format!("limit={limit}");
+    params.push(position);
+    params.push(&limit);
+
+    format!("<{}?{}>; rel=\"{rel}\"", uri.path(), params.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Item {
+        id: String,
+        created_at: DateTime<Utc>,
+    }
+
+    fn items(count: usize) -> Vec<Item> {
+        (0..count)
+            .map(|i| Item {
+                id: format!("pst_{i}"),
+                created_at: DateTime::from_timestamp(1_700_000_000 - i as i64, 0).unwrap(),
+            })
+            .collect()
+    }
+
+    fn link_header(uri: &str, items: Vec<Item>, page: &Page, limit: i64) -> Option<String> {
+        let uri: Uri = uri.parse().unwrap();
+
+        let response = paginated(&uri, items, page, limit, None, |item| {
+            Cursor::new(item.created_at, &item.id)
+        });
+
+        response
+            .headers()
+            .get(header::LINK)
+            .map(|value| value.to_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn offset_pages_link_to_next_and_prev() {
+        let link = link_header(
+            "/posts?tag=rust&offset=20&limit=10",
+            items(11),
+            &Page::Offset(20),
+            10,
+        );
+
+        assert_eq!(
+            link.as_deref(),
+            Some(
+                "</posts?tag=rust&offset=30&limit=10>; rel=\"next\", \
+                 </posts?tag=rust&offset=10&limit=10>; rel=\"prev\""
+            )
+        );
+    }
+
+    #[test]
+    fn first_and_last_offset_pages_have_one_link() {
+        let first = link_header("/posts", items(11), &Page::Offset(0), 10);
+        let last = link_header("/posts?offset=5&limit=10", items(3), &Page::Offset(5), 10);
+
+        assert_eq!(
+            first.as_deref(),
+            Some("</posts?offset=10&limit=10>; rel=\"next\"")
+        );
+        assert_eq!(
+            last.as_deref(),
+            Some("</posts?offset=0&limit=10>; rel=\"prev\"")
+        );
+    }
+
+    #[test]
+    fn cursor_pages_only_link_forward() {
+        let items = items(3);
+        let next_cursor = Cursor::new(items[1].created_at, &items[1].id).encode();
+
+        let link = link_header("/feed?cursor=&limit=2", items, &Page::After(None), 2);
+
+        assert_eq!(
+            link,
+            Some(format!(
+                "</feed?cursor={next_cursor}&limit=2>; rel=\"next\""
+            ))
+        );
+    }
+
+    #[test]
+    fn single_pages_have_no_link() {
+        assert!(link_header("/posts", items(2), &Page::Offset(0), 10).is_none());
+        assert!(link_header("/feed", items(2), &Page::After(None), 10).is_none());
+    }
+
+    #[test]
+    fn push_page_pages_by_offset() {
+        let mut query_builder = QueryBuilder::new("SELECT * FROM posts p WHERE TRUE");
+
+        push_page(&mut query_builder, "p", &Page::Offset(20), 10);
+
+        assert_eq!(
+            query_builder.sql(),
+            "SELECT * FROM posts p WHERE TRUE \
+             ORDER BY p.created_at DESC, p.id DESC OFFSET $1 LIMIT $2"
+        );
+    }
+
+    #[test]
+    fn push_page_pages_after_the_cursor() {
+        let mut query_builder = QueryBuilder::new("SELECT * FROM posts p WHERE TRUE");
+        let cursor = Cursor::new(Utc::now(), "pst_1");
+
+        push_page(&mut query_builder, "p", &Page::After(Some(cursor)), 10);
+
+        assert_eq!(
+            query_builder.sql(),
+            "SELECT * FROM posts p WHERE TRUE AND (p.created_at, p.id) < ($1, $2) \
+             ORDER BY p.created_at DESC, p.id DESC LIMIT $3"
+        );
+    }
+}
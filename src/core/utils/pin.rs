@@ -7,3 +7,16 @@ pub fn generate_pin() -> String {
 
     pin.to_string()
 }
+
+/// Compares pins in constant time, so response times don't tell how many leading digits
+/// of a guess were right.
+pub fn pins_match(pin: &str, candidate: &str) -> bool {
+    let (pin, candidate) = (pin.as_bytes(), candidate.as_bytes());
+
+    pin.len() == candidate.len()
+        && pin
+            .iter()
+            .zip(candidate)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
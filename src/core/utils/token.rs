@@ -0,0 +1,41 @@
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+const OPAQUE_TOKEN_BYTES: usize = 32;
+
+/// Generates a random, url-safe token that is handed out to clients as-is
+/// and only ever persisted as a hash (see [`hash_token`]).
+pub fn generate_opaque_token() -> String {
+    let mut bytes = [0u8; OPAQUE_TOKEN_BYTES];
+
+    rand::rng().fill_bytes(&mut bytes);
+
+    to_hex(&bytes)
+}
+
+pub fn hash_token(token: &str) -> String {
+    let digest = Sha256::digest(token.as_bytes());
+
+    to_hex(&digest)
+}
+
+/// Generates a one-time recovery code in the `xxxxx-xxxxx` format.
+pub fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 5];
+
+    rand::rng().fill_bytes(&mut bytes);
+
+    let code = to_hex(&bytes);
+
+    format!("{}-{}", &code[..5], &code[5..])
+}
+
+/// Recovery codes are compared case-insensitively and with surrounding whitespace
+/// removed, since users usually type them in by hand.
+pub fn hash_recovery_code(code: &str) -> String {
+    hash_token(&code.trim().to_lowercase())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,172 @@
+//! Signed tokens of the one-click unsubscribe links in notification emails. They
+//! don't expire and are only valid for the user and scope they were issued for.
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use ring::hmac;
+
+use crate::{config::CONFIG, constants::SERVER_URL, models::NotificationKind};
+
+/// What an unsubscribe link turns off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsubscribeScope {
+    Digest,
+    /// Emails of a notification kind, which keeps being delivered in-app.
+    Email(NotificationKind),
+}
+
+impl UnsubscribeScope {
+    fn to_str(self) -> &'static str {
+        match self {
+            UnsubscribeScope::Digest => "digest",
+            UnsubscribeScope::Email(kind) => kind.to_str(),
+        }
+    }
+
+    fn parse(scope: &str) -> Option<Self> {
+        match scope {
+            "digest" => Some(UnsubscribeScope::Digest),
+            kind => NotificationKind::ALL
+                .into_iter()
+                .find(|k| k.to_str() == kind)
+                .map(UnsubscribeScope::Email),
+        }
+    }
+}
+
+pub fn sign(user_id: &str, scope: UnsubscribeScope) -> String {
+    sign_with(&key(), user_id, scope)
+}
+
+/// Returns the user and scope of a token signed with [`sign`].
+pub fn verify(token: &str) -> Option<(String, UnsubscribeScope)> {
+    verify_with(&key(), token)
+}
+
+pub fn unsubscribe_url(user_id: &str, scope: UnsubscribeScope) -> String {
+    format!(
+        "{}/notifications/unsubscribe?token={}",
+        *SERVER_URL,
+        sign(user_id, scope)
+    )
+}
+
+fn key() -> hmac::Key {
+    hmac::Key::new(hmac::HMAC_SHA256, CONFIG.unsubscribe_secret.as_bytes())
+}
+
+fn sign_with(key: &hmac::Key, user_id: &str, scope: UnsubscribeScope) -> String {
+    let payload = format!("{}|{}", user_id, scope.to_str());
+    let tag = hmac::sign(key, payload.as_bytes());
+
+    format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(payload),
+        URL_SAFE_NO_PAD.encode(tag.as_ref())
+    )
+}
+
+fn verify_with(key: &hmac::Key, token: &str) -> Option<(String, UnsubscribeScope)> {
+    let (payload, tag) = token.split_once('.')?;
+    let payload = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let tag = URL_SAFE_NO_PAD.decode(tag).ok()?;
+
+    hmac::verify(key, &payload, &tag).ok()?;
+
+    let payload = String::from_utf8(payload).ok()?;
+    let (user_id, scope) = payload.split_once('|')?;
+
+    Some((user_id.to_string(), UnsubscribeScope::parse(scope)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key() -> hmac::Key {
+        hmac::Key::new(hmac::HMAC_SHA256, b"test-unsubscribe-secret")
+    }
+
+    /// Swaps the payload of `token` for `payload`, keeping its tag.
+    fn with_payload(token: &str, payload: &str) -> String {
+        let (_, tag) = token.split_once('.').unwrap();
+
+        format!("{}.{}", URL_SAFE_NO_PAD.encode(payload), tag)
+    }
+
+    #[test]
+    fn round_trips() {
+        let key = test_key();
+
+        for scope in [
+            UnsubscribeScope::Digest,
+            UnsubscribeScope::Email(NotificationKind::Mention),
+        ] {
+            let token = sign_with(&key, "usr_1", scope);
+
+            assert_eq!(
+                verify_with(&key, &token),
+                Some(("usr_1".to_string(), scope))
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_a_tampered_payload() {
+        let key = test_key();
+        let token = sign_with(
+            &key,
+            "usr_1",
+            UnsubscribeScope::Email(NotificationKind::Like),
+        );
+
+        let tampered = with_payload(&token, "usr_1|digest");
+
+        assert_eq!(verify_with(&key, &tampered), None);
+    }
+
+    #[test]
+    fn rejects_a_tampered_tag() {
+        let key = test_key();
+        let token = sign_with(&key, "usr_1", UnsubscribeScope::Digest);
+
+        let (payload, tag) = token.split_once('.').unwrap();
+        let mut tag = URL_SAFE_NO_PAD.decode(tag).unwrap();
+        tag[0] ^= 1;
+        let tampered = format!("{}.{}", payload, URL_SAFE_NO_PAD.encode(tag));
+
+        assert_eq!(verify_with(&key, &tampered), None);
+        assert_eq!(verify_with(&key, payload), None);
+    }
+
+    #[test]
+    fn rejects_a_swapped_user_id() {
+        let key = test_key();
+        let token = sign_with(&key, "usr_1", UnsubscribeScope::Digest);
+
+        let swapped = with_payload(&token, "usr_2|digest");
+
+        assert_eq!(verify_with(&key, &swapped), None);
+    }
+
+    #[test]
+    fn rejects_a_token_signed_with_another_key() {
+        let other_key = hmac::Key::new(hmac::HMAC_SHA256, b"another-secret");
+        let token = sign_with(&other_key, "usr_1", UnsubscribeScope::Digest);
+
+        assert_eq!(verify_with(&test_key(), &token), None);
+    }
+
+    #[test]
+    fn rejects_an_unknown_scope() {
+        let key = test_key();
+        let payload = "usr_1|everything";
+        let tag = hmac::sign(&key, payload.as_bytes());
+        let token = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(payload),
+            URL_SAFE_NO_PAD.encode(tag.as_ref())
+        );
+
+        assert_eq!(verify_with(&key, &token), None);
+    }
+}
@@ -0,0 +1,19 @@
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::models::{AccountStatus, UserRole};
+
+#[derive(Deserialize)]
+pub struct UpdateUserRoleDto {
+    pub role: UserRole,
+}
+
+#[derive(Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateAccountStatusDto {
+    pub status: AccountStatus,
+    /// Required when suspending, ignored otherwise.
+    pub suspended_until: Option<chrono::DateTime<chrono::Utc>>,
+    #[validate(length(max = 500, message = "Reason must be at most 500 characters"))]
+    pub reason: Option<String>,
+}
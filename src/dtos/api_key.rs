@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::models::{ApiKey, ApiKeyScope};
+
+#[derive(Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyDto {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Name's length must be between 1 and 100 characters"
+    ))]
+    pub name: String,
+    #[validate(length(min = 1, message = "At least one scope is required"))]
+    pub scopes: Vec<ApiKeyScope>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct CreateApiKeyResponseDto {
+    #[serde(flatten)]
+    pub api_key: ApiKey,
+    pub token: String,
+}
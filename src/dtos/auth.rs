@@ -23,7 +23,42 @@ pub struct VerifyEmailDto {
 }
 
 #[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct VerifyResponseDto {
     pub token: String,
+    pub refresh_token: String,
     pub user: User,
 }
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshTokenDto {
+    #[validate(length(min = 1, message = "Refresh token is required"))]
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshTokenResponseDto {
+    pub token: String,
+    pub refresh_token: String,
+}
+
+/// Returned by `verify_email` instead of a session when the user has two-factor
+/// authentication enabled.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwoFactorChallengeResponseDto {
+    pub two_factor_required: bool,
+    pub challenge_token: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyTwoFactorDto {
+    #[validate(length(min = 1, message = "Challenge token is required"))]
+    pub challenge_token: String,
+    #[validate(length(min = 1, message = "Code is required"))]
+    pub code: String,
+}
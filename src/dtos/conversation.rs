@@ -0,0 +1,49 @@
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::models::MediaType;
+
+#[derive(Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateConversationDto {
+    /// The other participants, more than one makes a group conversation.
+    pub participant_ids: Vec<String>,
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Title's length must be between 1 and 100 characters"
+    ))]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all(deserialize = "camelCase"))]
+pub struct MessageAttachmentDto {
+    /// Url returned by the upload endpoint.
+    pub url: String,
+    pub r#type: MediaType,
+    pub mime_type: String,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub size: Option<i32>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct SendMessageDto {
+    /// May be empty when the message has attachments.
+    #[serde(default)]
+    #[validate(length(max = 10000, message = "Content can't exceed 10000 characters"))]
+    pub content: String,
+    #[serde(default = "Vec::new")]
+    pub attachments: Vec<MessageAttachmentDto>,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct UpdateMessageDto {
+    #[validate(length(
+        min = 1,
+        max = 10000,
+        message = "Content characters must be between 1 and 10000"
+    ))]
+    pub content: String,
+}
@@ -1,4 +1,10 @@
+pub mod admin;
+pub mod api_key;
 pub mod auth;
 pub mod comment;
+pub mod conversation;
+pub mod notification;
 pub mod post;
+pub mod search;
+pub mod two_factor;
 pub mod user;
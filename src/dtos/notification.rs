@@ -0,0 +1,24 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{DigestFrequency, NotificationDelivery, NotificationKind};
+
+#[derive(Deserialize)]
+pub struct MarkNotificationsReadDto {
+    /// Every notification is marked as read when missing.
+    pub ids: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateNotificationPreferencesDto {
+    /// Kinds left out keep their delivery.
+    pub deliveries: Option<HashMap<NotificationKind, NotificationDelivery>>,
+    pub digest: Option<DigestFrequency>,
+}
+
+#[derive(Serialize)]
+pub struct NotificationPreferencesDto {
+    pub deliveries: HashMap<NotificationKind, NotificationDelivery>,
+    pub digest: DigestFrequency,
+}
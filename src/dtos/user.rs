@@ -1,6 +1,8 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::models::Session;
+
 #[derive(Deserialize, Validate)]
 pub struct UpdateProfileDto {
     #[validate(length(min = 1, message = "Username is required"))]
@@ -8,3 +10,24 @@ pub struct UpdateProfileDto {
     #[validate(url(message = "Invalid profile image url"))]
     pub profile_image_url: Option<String>,
 }
+
+#[derive(Serialize)]
+pub struct SessionResponseDto {
+    #[serde(flatten)]
+    pub session: Session,
+    pub current: bool,
+}
+
+#[derive(Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestEmailChangeDto {
+    #[validate(length(min = 1, message = "Email is required"))]
+    #[validate(email(message = "Invalid email"))]
+    pub new_email: String,
+}
+
+#[derive(Deserialize, Validate)]
+pub struct ConfirmEmailChangeDto {
+    #[validate(length(min = 1, message = "Pin is required"))]
+    pub pin: String,
+}
@@ -10,11 +10,14 @@ mod router;
 mod service;
 mod types;
 
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 
 use anyhow::Context;
 use app_state::AppState;
 use config::CONFIG;
+use core::services::{
+    event_hub::EventHub, jwt_keys::JwtKeyStore, mail::MailService, storage::StorageProvider,
+};
 use sqlx::postgres::PgPoolOptions;
 use tokio::net::TcpListener;
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
@@ -35,7 +38,27 @@ async fn main() {
         .context("Failed to connect to database")
         .unwrap();
 
-    let app = router::api_router().with_state(Arc::new(AppState::new(db)));
+    let jwt_keys = Arc::new(JwtKeyStore::new());
+
+    jwt_keys.clone().spawn_rotation();
+
+    core::services::account_purge::spawn_account_purge(
+        db.clone(),
+        Arc::new(StorageProvider::new()),
+    );
+
+    core::services::notification_mail::spawn_notification_mail(
+        db.clone(),
+        Arc::new(MailService::new()),
+    );
+
+    let events = EventHub::new();
+
+    events.spawn_listener(db.clone());
+
+    let app_state = Arc::new(AppState::new(db, jwt_keys, events));
+
+    let app = router::api_router(app_state.clone()).with_state(app_state);
 
     let listener = TcpListener::bind(format!("localhost:{}", CONFIG.port))
         .await
@@ -43,5 +66,10 @@ async fn main() {
 
     tracing::info!("Listening on {}", listener.local_addr().unwrap());
 
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
@@ -1,3 +1,5 @@
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::{FromRow, Type};
 
@@ -50,6 +52,122 @@ impl MediaType {
     }
 }
 
+#[derive(Debug, Type, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[sqlx(type_name = "UserRole", rename_all = "lowercase")]
+pub enum UserRole {
+    User,
+    Moderator,
+    Admin,
+}
+
+impl<'de> Deserialize<'de> for UserRole {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        match s.as_str() {
+            "user" => Ok(UserRole::User),
+            "moderator" => Ok(UserRole::Moderator),
+            "admin" => Ok(UserRole::Admin),
+            _ => Err(serde::de::Error::custom("Invalid user role")),
+        }
+    }
+}
+
+impl Serialize for UserRole {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_str())
+    }
+}
+
+impl UserRole {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            UserRole::User => "user",
+            UserRole::Moderator => "moderator",
+            UserRole::Admin => "admin",
+        }
+    }
+}
+
+#[derive(Debug, Type, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "AccountStatus", rename_all = "lowercase")]
+pub enum AccountStatus {
+    Active,
+    Suspended,
+    Banned,
+}
+
+impl<'de> Deserialize<'de> for AccountStatus {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        match s.as_str() {
+            "active" => Ok(AccountStatus::Active),
+            "suspended" => Ok(AccountStatus::Suspended),
+            "banned" => Ok(AccountStatus::Banned),
+            _ => Err(serde::de::Error::custom("Invalid account status")),
+        }
+    }
+}
+
+impl Serialize for AccountStatus {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_str())
+    }
+}
+
+impl AccountStatus {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            AccountStatus::Active => "active",
+            AccountStatus::Suspended => "suspended",
+            AccountStatus::Banned => "banned",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountState {
+    pub status: AccountStatus,
+    pub suspended_until: Option<chrono::DateTime<chrono::Utc>>,
+    pub status_reason: Option<String>,
+}
+
+impl AccountState {
+    /// Explains why the account can't be used right now, `None` if it can.
+    ///
+    /// Suspensions lift themselves once `suspended_until` passes.
+    pub fn restriction(&self) -> Option<String> {
+        let restriction = match (self.status, self.suspended_until) {
+            (AccountStatus::Active, _) => return None,
+            (AccountStatus::Suspended, Some(until)) if until <= chrono::Utc::now() => return None,
+            (AccountStatus::Suspended, Some(until)) => {
+                format!("Your account is suspended until {}", until.to_rfc3339())
+            }
+            (AccountStatus::Suspended, None) => "Your account is suspended".to_string(),
+            (AccountStatus::Banned, _) => "Your account has been banned".to_string(),
+        };
+
+        match &self.status_reason {
+            Some(reason) => Some(format!("{}. Reason: {}", restriction, reason)),
+            None => Some(restriction),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct User {
@@ -58,10 +176,356 @@ pub struct User {
     pub username: String,
     pub profile_image_url: Option<String>,
     pub is_verified: bool,
+    pub role: UserRole,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub account_state: AccountState,
+    pub deletion_scheduled_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// Author of a post or comment, as anyone can see it. The email and the moderation
+/// and deletion state of the account are left out.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Author {
+    pub id: String,
+    pub username: String,
+    pub profile_image_url: Option<String>,
+    pub is_verified: bool,
+    pub role: UserRole,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<User> for Author {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            profile_image_url: user.profile_image_url,
+            is_verified: user.is_verified,
+            role: user.role,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
+
+/// What anyone can see about a user, without private fields such as the email.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicProfile {
+    pub id: String,
+    pub username: String,
+    pub profile_image_url: Option<String>,
+    pub role: UserRole,
+    pub posts_count: i64,
+    pub comments_count: i64,
+    pub likes_received: i64,
+    pub followers_count: i64,
+    pub following_count: i64,
+    pub joined_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A user in a followers or following list.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct FollowListEntry {
+    pub id: String,
+    pub username: String,
+    pub profile_image_url: Option<String>,
+    pub followed_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A user matching a username search.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct UserSearchResult {
+    pub id: String,
+    pub username: String,
+    pub profile_image_url: Option<String>,
+    /// Only set when the search was made by a signed in user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub followed_by_me: Option<bool>,
+}
+
+/// A post or comment matching a full-text search.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    /// Either `post` or `comment`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub id: String,
+    pub post_id: String,
+    pub user_id: String,
+    pub username: String,
+    /// Title of the post, or of the commented post.
+    pub title: String,
+    /// HTML excerpt of the content with the matched terms wrapped in `<mark>` tags,
+    /// the content itself is escaped.
+    pub snippet: String,
+    pub rank: f32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendingTag {
+    pub name: String,
+    pub posts_count: i64,
+    pub authors_count: i64,
+}
+
+/// Owner of an active session, as seen by the auth layer.
+#[derive(Debug, FromRow)]
+pub struct SessionOwner {
+    pub id: String,
+    pub role: UserRole,
+    #[sqlx(flatten)]
+    pub account_state: AccountState,
+}
+
+#[derive(Debug, Type, Clone, Copy, PartialEq, Eq, Hash)]
+#[sqlx(type_name = "NotificationKind", rename_all = "lowercase")]
+pub enum NotificationKind {
+    Like,
+    Reply,
+    Mention,
+    Follow,
+}
+
+impl<'de> Deserialize<'de> for NotificationKind {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        match s.as_str() {
+            "like" => Ok(NotificationKind::Like),
+            "reply" => Ok(NotificationKind::Reply),
+            "mention" => Ok(NotificationKind::Mention),
+            "follow" => Ok(NotificationKind::Follow),
+            _ => Err(serde::de::Error::custom("Invalid notification kind")),
+        }
+    }
+}
+
+impl Serialize for NotificationKind {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_str())
+    }
+}
+
+impl NotificationKind {
+    pub const ALL: [NotificationKind; 4] = [
+        NotificationKind::Like,
+        NotificationKind::Reply,
+        NotificationKind::Mention,
+        NotificationKind::Follow,
+    ];
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            NotificationKind::Like => "like",
+            NotificationKind::Reply => "reply",
+            NotificationKind::Mention => "mention",
+            NotificationKind::Follow => "follow",
+        }
+    }
+}
+
+/// How notifications of a kind reach the user, emailed ones are also listed in-app.
+#[derive(Debug, Type, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "NotificationDelivery", rename_all = "lowercase")]
+pub enum NotificationDelivery {
+    InApp,
+    Email,
+    None,
+}
+
+impl<'de> Deserialize<'de> for NotificationDelivery {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        match s.as_str() {
+            "inapp" => Ok(NotificationDelivery::InApp),
+            "email" => Ok(NotificationDelivery::Email),
+            "none" => Ok(NotificationDelivery::None),
+            _ => Err(serde::de::Error::custom("Invalid notification delivery")),
+        }
+    }
+}
+
+impl Serialize for NotificationDelivery {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_str())
+    }
+}
+
+impl NotificationDelivery {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            NotificationDelivery::InApp => "inapp",
+            NotificationDelivery::Email => "email",
+            NotificationDelivery::None => "none",
+        }
+    }
+}
+
+#[derive(Debug, Type, Clone, Copy, PartialEq, Eq)]
+#[sqlx(type_name = "DigestFrequency", rename_all = "lowercase")]
+pub enum DigestFrequency {
+    None,
+    Daily,
+    Weekly,
+}
+
+impl<'de> Deserialize<'de> for DigestFrequency {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        match s.as_str() {
+            "none" => Ok(DigestFrequency::None),
+            "daily" => Ok(DigestFrequency::Daily),
+            "weekly" => Ok(DigestFrequency::Weekly),
+            _ => Err(serde::de::Error::custom("Invalid digest frequency")),
+        }
+    }
+}
+
+impl Serialize for DigestFrequency {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_str())
+    }
+}
+
+impl DigestFrequency {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            DigestFrequency::None => "none",
+            DigestFrequency::Daily => "daily",
+            DigestFrequency::Weekly => "weekly",
+        }
+    }
+}
+
+/// Events of the same kind on the same target, grouped while unread. `post_id` is
+/// the liked or mentioning post, `comment_id` the replied to or mentioning comment.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    pub id: String,
+    pub kind: NotificationKind,
+    pub post_id: Option<String>,
+    pub comment_id: Option<String>,
+    pub actors_count: i64,
+    pub read_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationActor {
+    #[serde(skip)]
+    pub notification_id: String,
+    pub id: String,
+    pub username: String,
+    pub profile_image_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationDetails {
+    #[serde(flatten)]
+    pub notification: Notification,
+    /// The latest actors, see `actors_count` for how many there are.
+    pub actors: Vec<NotificationActor>,
+    /// Such as "alice and 12 others liked your post".
+    pub summary: String,
+}
+
+impl NotificationDetails {
+    pub fn new(notification: Notification, actors: Vec<NotificationActor>) -> Self {
+        let who = match (actors.first(), notification.actors_count) {
+            (None, _) => "Someone".to_string(),
+            (Some(actor), 1) => actor.username.clone(),
+            (Some(actor), 2) => match actors.get(1) {
+                Some(other) => format!("{} and {}", actor.username, other.username),
+                None => format!("{} and 1 other", actor.username),
+            },
+            (Some(actor), count) => format!("{} and {} others", actor.username, count - 1),
+        };
+
+        let what = match (notification.kind, notification.comment_id.is_some()) {
+            (NotificationKind::Like, _) => "liked your post",
+            (NotificationKind::Reply, _) => "replied to your comment",
+            (NotificationKind::Mention, false) => "mentioned you in a post",
+            (NotificationKind::Mention, true) => "mentioned you in a comment",
+            (NotificationKind::Follow, _) => "followed you",
+        };
+
+        Self {
+            summary: format!("{who} {what}"),
+            notification,
+            actors,
+        }
+    }
+}
+
+/// An emailed notification waiting to be sent.
+#[derive(Debug, FromRow)]
+pub struct PendingNotificationMail {
+    pub notification_id: String,
+    pub user_id: String,
+    pub email: String,
+}
+
+/// A user whose activity digest is due, covering activity from `last_sent_at` to
+/// `until`.
+#[derive(Debug, FromRow)]
+pub struct DueDigest {
+    pub user_id: String,
+    pub email: String,
+    pub frequency: DigestFrequency,
+    pub last_sent_at: chrono::DateTime<chrono::Utc>,
+    pub until: chrono::DateTime<chrono::Utc>,
+}
+
+/// New likes and comments on one of the posts of a digest.
+#[derive(Debug, FromRow)]
+pub struct DigestPostActivity {
+    pub title: String,
+    pub likes_count: i64,
+    pub comments_count: i64,
+}
+
+#[derive(Debug)]
+pub struct DigestActivity {
+    pub posts: Vec<DigestPostActivity>,
+    pub followers_count: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 #[serde(rename_all = "camelCase")]
 pub struct VerificationPin {
@@ -69,9 +533,141 @@ pub struct VerificationPin {
     pub email: String,
     pub pin: String,
     pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub invalidated_at: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub id: String,
+    pub user_id: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub last_seen_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshToken {
+    pub id: String,
+    pub session_id: String,
+    pub token_hash: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiKeyScope {
+    PostsRead,
+    PostsWrite,
+    CommentsRead,
+    CommentsWrite,
+    ProfileRead,
+    ProfileWrite,
+    UploadsWrite,
+}
+
+impl<'de> Deserialize<'de> for ApiKeyScope {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        ApiKeyScope::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl Serialize for ApiKeyScope {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.to_str())
+    }
+}
+
+impl FromStr for ApiKeyScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "posts:read" => Ok(ApiKeyScope::PostsRead),
+            "posts:write" => Ok(ApiKeyScope::PostsWrite),
+            "comments:read" => Ok(ApiKeyScope::CommentsRead),
+            "comments:write" => Ok(ApiKeyScope::CommentsWrite),
+            "profile:read" => Ok(ApiKeyScope::ProfileRead),
+            "profile:write" => Ok(ApiKeyScope::ProfileWrite),
+            "uploads:write" => Ok(ApiKeyScope::UploadsWrite),
+            _ => Err(format!("Invalid api key scope: {}", s)),
+        }
+    }
+}
+
+impl ApiKeyScope {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            ApiKeyScope::PostsRead => "posts:read",
+            ApiKeyScope::PostsWrite => "posts:write",
+            ApiKeyScope::CommentsRead => "comments:read",
+            ApiKeyScope::CommentsWrite => "comments:write",
+            ApiKeyScope::ProfileRead => "profile:read",
+            ApiKeyScope::ProfileWrite => "profile:write",
+            ApiKeyScope::UploadsWrite => "uploads:write",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKey {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, FromRow, Clone)]
+pub struct EmailChangeRequest {
+    pub id: String,
+    pub new_email: String,
+    pub pin: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, FromRow, Clone)]
+pub struct TotpCredential {
+    pub user_id: String,
+    pub secret: String,
+    pub enabled_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Pending second step of a login for a user with two-factor authentication enabled.
+#[derive(Debug, FromRow, Clone)]
+pub struct TwoFactorChallenge {
+    pub id: String,
+    pub user_id: String,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ApiKey {
+    pub fn has_scope(&self, scope: ApiKeyScope) -> bool {
+        self.scopes.iter().any(|s| s == scope.to_str())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Post {
@@ -98,14 +694,40 @@ pub struct PostMedia {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// A mentioned user in the content of a post or comment. `start` and `end` are byte
+/// offsets of the `@username` in the content as it was written, `username` is the
+/// current one and may differ after a rename.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Mention {
+    /// Id of the post or comment the mention belongs to.
+    #[serde(skip)]
+    pub target_id: String,
+    pub user_id: String,
+    pub username: String,
+    pub start: i32,
+    pub end: i32,
+}
+
+/// What the requesting user has done with a post. Only present when the request
+/// is authenticated.
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PostViewerContext {
+    pub liked_by_me: bool,
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct PostDetails {
     #[serde(flatten)]
     pub post: Post,
-    pub author: User,
+    pub author: Author,
     pub media: Vec<PostMedia>,
+    pub mentions: Vec<Mention>,
     pub likes_count: i64,
     pub comments_count: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub viewer: Option<PostViewerContext>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -135,5 +757,76 @@ pub struct PostComment {
 pub struct PostCommentDetails {
     #[serde(flatten)]
     pub comment: PostComment,
-    pub author: User,
+    pub author: Author,
+    pub mentions: Vec<Mention>,
+}
+
+/// A one-to-one or group conversation as listed for one of its participants.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Conversation {
+    pub id: String,
+    pub title: Option<String>,
+    pub is_group: bool,
+    pub created_by: Option<String>,
+    /// Messages of the other participants sent after the user's read receipt.
+    pub unread_count: i64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_message_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationParticipant {
+    #[serde(skip)]
+    pub conversation_id: String,
+    pub id: String,
+    pub username: String,
+    pub profile_image_url: Option<String>,
+    /// Every message sent up to then was read by the participant.
+    pub last_read_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationDetails {
+    #[serde(flatten)]
+    pub conversation: Conversation,
+    pub participants: Vec<ConversationParticipant>,
+    pub last_message: Option<MessageDetails>,
+}
+
+/// Deleted messages are kept with an empty content so the history shows where they
+/// were.
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+    pub id: String,
+    pub conversation_id: String,
+    pub user_id: String,
+    pub content: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageAttachment {
+    pub id: String,
+    pub message_id: String,
+    pub media_url: String,
+    pub media_type: MediaType,
+    pub mime_type: String,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub file_size: Option<i32>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MessageDetails {
+    #[serde(flatten)]
+    pub message: Message,
+    pub attachments: Vec<MessageAttachment>,
 }
@@ -0,0 +1,22 @@
+use crate::{controllers, core::layers::auth_layer::AuthPolicy};
+
+use super::routes::Routes;
+
+pub fn routes() -> Routes {
+    Routes::nest("/admin")
+        .get(
+            "/users",
+            controllers::admin::get_users,
+            AuthPolicy::authenticated(),
+        )
+        .patch(
+            "/users/{user_id}/role",
+            controllers::admin::update_user_role,
+            AuthPolicy::authenticated(),
+        )
+        .patch(
+            "/users/{user_id}/status",
+            controllers::admin::update_account_status,
+            AuthPolicy::authenticated(),
+        )
+}
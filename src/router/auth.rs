@@ -1,13 +1,28 @@
-use crate::{app_state::SharedAppState, controllers};
-use axum::{Router, routing::post};
+use crate::{controllers, core::layers::auth_layer::AuthPolicy};
 
-pub fn router() -> Router<SharedAppState> {
-    let base = Router::new();
+use super::routes::Routes;
 
-    base.nest(
-        "/auth",
-        Router::new()
-            .route("/login", post(controllers::auth::login))
-            .route("/verify", post(controllers::auth::verify_email)),
-    )
+pub fn routes() -> Routes {
+    Routes::nest("/auth")
+        .post("/login", controllers::auth::login, AuthPolicy::public())
+        .post(
+            "/verify",
+            controllers::auth::verify_email,
+            AuthPolicy::public(),
+        )
+        .post(
+            "/verify/2fa",
+            controllers::auth::verify_two_factor,
+            AuthPolicy::public(),
+        )
+        .post(
+            "/refresh",
+            controllers::auth::refresh_token,
+            AuthPolicy::public(),
+        )
+        .post(
+            "/logout",
+            controllers::auth::logout,
+            AuthPolicy::authenticated(),
+        )
 }
@@ -1,27 +1,32 @@
-use axum::{
-    Router,
-    routing::{delete, get, patch, post},
-};
+use crate::{controllers, core::layers::auth_layer::AuthPolicy, models::ApiKeyScope};
 
-use crate::{app_state::SharedAppState, controllers};
+use super::routes::Routes;
 
-pub fn router() -> Router<SharedAppState> {
-    Router::new().nest(
-        "/comments",
-        Router::new()
-            .route(
-                "/post/{post_id}",
-                get(controllers::comment::get_posts_comments),
-            )
-            .route(
-                "/user/{user_id}",
-                get(controllers::comment::get_user_comments),
-            )
-            .route("/", post(controllers::comment::create_comment))
-            .route("/{comment_id}", patch(controllers::comment::update_comment))
-            .route(
-                "/{comment_id}",
-                delete(controllers::comment::delete_comment),
-            ),
-    )
+pub fn routes() -> Routes {
+    Routes::nest("/comments")
+        .get(
+            "/post/{post_id}",
+            controllers::comment::get_posts_comments,
+            AuthPolicy::public().api_key(ApiKeyScope::CommentsRead),
+        )
+        .get(
+            "/user/{user_id}",
+            controllers::comment::get_user_comments,
+            AuthPolicy::public().api_key(ApiKeyScope::CommentsRead),
+        )
+        .post(
+            "/",
+            controllers::comment::create_comment,
+            AuthPolicy::authenticated().api_key(ApiKeyScope::CommentsWrite),
+        )
+        .patch(
+            "/{comment_id}",
+            controllers::comment::update_comment,
+            AuthPolicy::authenticated().api_key(ApiKeyScope::CommentsWrite),
+        )
+        .delete(
+            "/{comment_id}",
+            controllers::comment::delete_comment,
+            AuthPolicy::authenticated().api_key(ApiKeyScope::CommentsWrite),
+        )
 }
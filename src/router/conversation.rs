@@ -0,0 +1,47 @@
+use crate::{controllers, core::layers::auth_layer::AuthPolicy};
+
+use super::routes::Routes;
+
+pub fn routes() -> Routes {
+    Routes::nest("/conversations")
+        .get(
+            "/",
+            controllers::conversation::get_conversations,
+            AuthPolicy::authenticated(),
+        )
+        .post(
+            "/",
+            controllers::conversation::create_conversation,
+            AuthPolicy::authenticated(),
+        )
+        .get(
+            "/{conversation_id}",
+            controllers::conversation::get_conversation,
+            AuthPolicy::authenticated(),
+        )
+        .get(
+            "/{conversation_id}/messages",
+            controllers::conversation::get_messages,
+            AuthPolicy::authenticated(),
+        )
+        .post(
+            "/{conversation_id}/messages",
+            controllers::conversation::send_message,
+            AuthPolicy::authenticated(),
+        )
+        .patch(
+            "/{conversation_id}/messages/{message_id}",
+            controllers::conversation::update_message,
+            AuthPolicy::authenticated(),
+        )
+        .delete(
+            "/{conversation_id}/messages/{message_id}",
+            controllers::conversation::delete_message,
+            AuthPolicy::authenticated(),
+        )
+        .post(
+            "/{conversation_id}/read",
+            controllers::conversation::mark_as_read,
+            AuthPolicy::authenticated(),
+        )
+}
@@ -0,0 +1,11 @@
+use crate::{controllers, core::layers::auth_layer::AuthPolicy, models::ApiKeyScope};
+
+use super::routes::Routes;
+
+pub fn routes() -> Routes {
+    Routes::nest("/feed").get(
+        "/home",
+        controllers::feed::home_feed,
+        AuthPolicy::authenticated().api_key(ApiKeyScope::PostsRead),
+    )
+}
@@ -1,8 +1,18 @@
+mod admin;
 mod auth;
 mod comment;
+mod conversation;
+mod feed;
+mod notification;
 mod post;
+mod profile;
+mod routes;
+mod search;
+mod stream;
+mod tag;
 mod upload;
 mod user;
+mod well_known;
 
 use std::{any::Any, convert::Infallible, sync::Arc};
 
@@ -11,48 +21,69 @@ use axum::{
     extract::{DefaultBodyLimit, Request},
     http::StatusCode,
     response::{IntoResponse, Response},
-    routing::get,
 };
 use tower::ServiceBuilder;
 use tower_http::{
     catch_panic::CatchPanicLayer,
     cors::{AllowOrigin, CorsLayer},
-    services::ServeDir,
     timeout::TimeoutLayer,
     trace::TraceLayer,
 };
 
+use routes::Routes;
+
 use crate::{
     app_state::SharedAppState,
     config::{CONFIG, Env},
     constants,
     core::{
         error::http_error::HttpError,
-        layers::auth_layer,
+        layers::auth_layer::{self, AuthPolicy},
         services::{mail::MailService, storage::StorageProvider},
     },
 };
 
-pub fn api_router() -> Router<SharedAppState> {
-    let router = Router::new()
-        .route("/", get(healt_check))
-        .merge(auth::router())
-        .merge(upload::router())
-        .merge(user::router())
-        .merge(post::router())
-        .merge(comment::router())
+/// Every route of the api together with its auth policy.
+fn routes() -> Routes {
+    Routes::new()
+        .get("/", healt_check, AuthPolicy::public())
+        .merge(auth::routes())
+        .merge(admin::routes())
+        .merge(upload::routes())
+        .merge(user::routes())
+        .merge(post::routes())
+        .merge(profile::routes())
+        .merge(comment::routes())
+        .merge(feed::routes())
+        .merge(notification::routes())
+        .merge(conversation::routes())
+        .merge(stream::routes())
+        .merge(search::routes())
+        .merge(tag::routes())
+        .merge(well_known::routes())
+}
+
+pub fn api_router(app_state: SharedAppState) -> Router<SharedAppState> {
+    let routes = routes();
+
+    let policies = match routes.policies() {
+        Ok(policies) => policies,
+        Err(errors) => panic!("Invalid route auth policies:\n{}", errors.join("\n")),
+    };
+
+    let router = routes
+        .into_router()
         .layer(Extension(Arc::new(StorageProvider::new())))
         .layer(Extension(Arc::new(MailService::new())));
 
-    let router = init_layers(router).nest_service(
-        "/uploads",
-        ServeDir::new(constants::DISK_STORAGE_PATH.to_string()),
-    );
-
-    router
+    init_layers(router, app_state, policies)
 }
 
-fn init_layers(router: Router<SharedAppState>) -> Router<SharedAppState> {
+fn init_layers(
+    router: Router<SharedAppState>,
+    app_state: SharedAppState,
+    policies: auth_layer::RoutePolicies,
+) -> Router<SharedAppState> {
     let cors = CorsLayer::new().allow_origin(match CONFIG.env {
         Env::DEV => AllowOrigin::any(),
         Env::RELEASE => AllowOrigin::from(vec![constants::WEBSITE_URL.parse().unwrap()]),
@@ -64,8 +95,11 @@ fn init_layers(router: Router<SharedAppState>) -> Router<SharedAppState> {
                 .layer(CatchPanicLayer::custom(handle_panic))
                 .layer(TraceLayer::new_for_http())
                 .layer(cors)
-                .layer(TimeoutLayer::new(constants::REQUEST_TIMEOUT))
-                .layer(auth_layer::AuthLayer::new().except(auth_layer::ExcludedPaths::new()))
+                .layer(TimeoutLayer::with_status_code(
+                    StatusCode::REQUEST_TIMEOUT,
+                    constants::REQUEST_TIMEOUT,
+                ))
+                .layer(auth_layer::AuthLayer::new(app_state).policies(policies))
                 .layer(DefaultBodyLimit::max(CONFIG.request_body_limit)),
         )
         .fallback(handle_404)
@@ -108,5 +142,33 @@ fn handle_panic(err: Box<dyn Any + Send + 'static>) -> Response {
         "Internal Server Error".to_string()
     };
 
-    return HttpError::server_error(message).into_response();
+    HttpError::server_error(message).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+
+    use super::*;
+    use crate::models::ApiKeyScope;
+
+    #[test]
+    fn public_routes_are_declared_explicitly() {
+        let policies = routes().policies().unwrap();
+
+        assert_eq!(
+            policies.at(&Method::GET, "/posts/post_1"),
+            Some(AuthPolicy::public().api_key(ApiKeyScope::PostsRead))
+        );
+        assert_eq!(
+            policies.at(&Method::POST, "/auth/logout"),
+            Some(AuthPolicy::authenticated())
+        );
+        assert_eq!(
+            policies.at(&Method::GET, "/uploads/images/file.png"),
+            Some(AuthPolicy::public())
+        );
+        assert_eq!(policies.at(&Method::POST, "/uploads/file.png"), None);
+        assert_eq!(policies.at(&Method::GET, "/not-a-route"), None);
+    }
 }
@@ -0,0 +1,42 @@
+use crate::{controllers, core::layers::auth_layer::AuthPolicy};
+
+use super::routes::Routes;
+
+pub fn routes() -> Routes {
+    Routes::nest("/notifications")
+        .get(
+            "/",
+            controllers::notification::get_notifications,
+            AuthPolicy::authenticated(),
+        )
+        .get(
+            "/unread-count",
+            controllers::notification::get_unread_count,
+            AuthPolicy::authenticated(),
+        )
+        .post(
+            "/read",
+            controllers::notification::mark_as_read,
+            AuthPolicy::authenticated(),
+        )
+        .get(
+            "/preferences",
+            controllers::notification::get_preferences,
+            AuthPolicy::authenticated(),
+        )
+        .patch(
+            "/preferences",
+            controllers::notification::update_preferences,
+            AuthPolicy::authenticated(),
+        )
+        .get(
+            "/unsubscribe",
+            controllers::notification::unsubscribe_page,
+            AuthPolicy::public(),
+        )
+        .post(
+            "/unsubscribe",
+            controllers::notification::unsubscribe,
+            AuthPolicy::public(),
+        )
+}
@@ -1,20 +1,42 @@
-use axum::{
-    Router,
-    routing::{delete, get, patch, post},
-};
+use crate::{controllers, core::layers::auth_layer::AuthPolicy, models::ApiKeyScope};
 
-use crate::{app_state::SharedAppState, controllers};
+use super::routes::Routes;
 
-pub fn router() -> Router<SharedAppState> {
-    Router::new().nest(
-        "/posts",
-        Router::new()
-            .route("/", post(controllers::post::create_post))
-            .route("/", get(controllers::post::find_posts))
-            .route("/user/{user_id}", get(controllers::post::find_user_posts))
-            .route("/{post_id}", get(controllers::post::find_post_by_id))
-            .route("/{post_id}", patch(controllers::post::update_post))
-            .route("/{post_id}", delete(controllers::post::delete_post))
-            .route("/like/{post_id}", post(controllers::post::like_post)),
-    )
+pub fn routes() -> Routes {
+    Routes::nest("/posts")
+        .post(
+            "/",
+            controllers::post::create_post,
+            AuthPolicy::authenticated().api_key(ApiKeyScope::PostsWrite),
+        )
+        .get(
+            "/",
+            controllers::post::find_posts,
+            AuthPolicy::public().api_key(ApiKeyScope::PostsRead),
+        )
+        .get(
+            "/user/{user_id}",
+            controllers::post::find_user_posts,
+            AuthPolicy::public().api_key(ApiKeyScope::PostsRead),
+        )
+        .get(
+            "/{post_id}",
+            controllers::post::find_post_by_id,
+            AuthPolicy::public().api_key(ApiKeyScope::PostsRead),
+        )
+        .patch(
+            "/{post_id}",
+            controllers::post::update_post,
+            AuthPolicy::authenticated().api_key(ApiKeyScope::PostsWrite),
+        )
+        .delete(
+            "/{post_id}",
+            controllers::post::delete_post,
+            AuthPolicy::authenticated().api_key(ApiKeyScope::PostsWrite),
+        )
+        .post(
+            "/like/{post_id}",
+            controllers::post::like_post,
+            AuthPolicy::authenticated().api_key(ApiKeyScope::PostsWrite),
+        )
 }
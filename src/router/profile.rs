@@ -0,0 +1,42 @@
+use crate::{controllers, core::layers::auth_layer::AuthPolicy, models::ApiKeyScope};
+
+use super::routes::Routes;
+
+pub fn routes() -> Routes {
+    Routes::nest("/users")
+        .get(
+            "/search",
+            controllers::profile::search_users,
+            AuthPolicy::public().api_key(ApiKeyScope::ProfileRead),
+        )
+        .get(
+            "/{user_id}",
+            controllers::profile::get_profile,
+            AuthPolicy::public().api_key(ApiKeyScope::ProfileRead),
+        )
+        .get(
+            "/by-username/{username}",
+            controllers::profile::get_profile_by_username,
+            AuthPolicy::public().api_key(ApiKeyScope::ProfileRead),
+        )
+        .get(
+            "/{user_id}/followers",
+            controllers::follow::get_followers,
+            AuthPolicy::public().api_key(ApiKeyScope::ProfileRead),
+        )
+        .get(
+            "/{user_id}/following",
+            controllers::follow::get_following,
+            AuthPolicy::public().api_key(ApiKeyScope::ProfileRead),
+        )
+        .post(
+            "/{user_id}/follow",
+            controllers::follow::follow_user,
+            AuthPolicy::authenticated(),
+        )
+        .delete(
+            "/{user_id}/follow",
+            controllers::follow::unfollow_user,
+            AuthPolicy::authenticated(),
+        )
+}
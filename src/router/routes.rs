@@ -0,0 +1,167 @@
+use std::convert::Infallible;
+
+use axum::{
+    Router,
+    extract::Request,
+    handler::Handler,
+    http::Method,
+    response::IntoResponse,
+    routing::{self, MethodRouter},
+};
+use tower::Service;
+
+use crate::{
+    app_state::SharedAppState,
+    core::layers::auth_layer::{AuthPolicy, RoutePolicies},
+};
+
+struct RouteDeclaration {
+    method: Method,
+    path: String,
+    policy: AuthPolicy,
+}
+
+/// Router that requires an auth policy for every route it registers.
+///
+/// ```ignore
+/// Routes::nest("/posts")
+///     .get("/", controllers::post::find_posts, AuthPolicy::public())
+///     .post("/", controllers::post::create_post, AuthPolicy::authenticated())
+/// ```
+pub struct Routes {
+    prefix: &'static str,
+    router: Router<SharedAppState>,
+    declarations: Vec<RouteDeclaration>,
+}
+
+impl Routes {
+    pub fn new() -> Self {
+        Self::nest("")
+    }
+
+    /// Routes registered afterwards are mounted under `prefix`.
+    pub fn nest(prefix: &'static str) -> Self {
+        Self {
+            prefix,
+            router: Router::new(),
+            declarations: Vec::new(),
+        }
+    }
+
+    pub fn get<H, T>(self, path: &str, handler: H, policy: AuthPolicy) -> Self
+    where
+        H: Handler<T, SharedAppState>,
+        T: 'static,
+    {
+        self.route(Method::GET, path, routing::get(handler), policy)
+    }
+
+    pub fn post<H, T>(self, path: &str, handler: H, policy: AuthPolicy) -> Self
+    where
+        H: Handler<T, SharedAppState>,
+        T: 'static,
+    {
+        self.route(Method::POST, path, routing::post(handler), policy)
+    }
+
+    pub fn patch<H, T>(self, path: &str, handler: H, policy: AuthPolicy) -> Self
+    where
+        H: Handler<T, SharedAppState>,
+        T: 'static,
+    {
+        self.route(Method::PATCH, path, routing::patch(handler), policy)
+    }
+
+    pub fn delete<H, T>(self, path: &str, handler: H, policy: AuthPolicy) -> Self
+    where
+        H: Handler<T, SharedAppState>,
+        T: 'static,
+    {
+        self.route(Method::DELETE, path, routing::delete(handler), policy)
+    }
+
+    /// Mounts a service answering every GET under `path`, such as a file server.
+    pub fn nest_service<S>(mut self, path: &str, service: S, policy: AuthPolicy) -> Self
+    where
+        S: Service<Request, Error = Infallible> + Clone + Send + Sync + 'static,
+        S::Response: IntoResponse,
+        S::Future: Send + 'static,
+    {
+        let path = format!("{}{path}", self.prefix);
+
+        self.router = self.router.nest_service(&path, service);
+        self.declarations.push(RouteDeclaration {
+            method: Method::GET,
+            path: format!("{path}/{{*path}}"),
+            policy,
+        });
+        self
+    }
+
+    pub fn merge(mut self, other: Routes) -> Self {
+        self.router = self.router.merge(other.router);
+        self.declarations.extend(other.declarations);
+        self
+    }
+
+    fn route(
+        mut self,
+        method: Method,
+        path: &str,
+        method_router: MethodRouter<SharedAppState>,
+        policy: AuthPolicy,
+    ) -> Self {
+        let path = match (self.prefix, path) {
+            ("", path) => path.to_string(),
+            (prefix, "/") => prefix.to_string(),
+            (prefix, path) => format!("{prefix}{path}"),
+        };
+
+        self.router = self.router.route(&path, method_router);
+        self.declarations.push(RouteDeclaration {
+            method,
+            path,
+            policy,
+        });
+        self
+    }
+
+    /// Builds the policy table the auth layer enforces.
+    ///
+    /// Fails when a route is declared twice or when looking up a route resolves to
+    /// the policy of another one, so the layer would enforce something other than
+    /// what was declared next to the handler.
+    pub fn policies(&self) -> Result<RoutePolicies, Vec<String>> {
+        let mut policies = RoutePolicies::default();
+
+        let mut errors: Vec<String> = self
+            .declarations
+            .iter()
+            .filter_map(|route| {
+                policies
+                    .insert(&route.method, &route.path, route.policy)
+                    .err()
+            })
+            .collect();
+
+        errors.extend(self.declarations.iter().filter_map(|route| {
+            match policies.at(&route.method, &route.path) {
+                Some(policy) if policy == route.policy => None,
+                _ => Some(format!(
+                    "{} {}: resolves to a different auth policy than declared",
+                    route.method, route.path
+                )),
+            }
+        }));
+
+        if errors.is_empty() {
+            Ok(policies)
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn into_router(self) -> Router<SharedAppState> {
+        self.router
+    }
+}
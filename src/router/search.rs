@@ -0,0 +1,11 @@
+use crate::{controllers, core::layers::auth_layer::AuthPolicy, models::ApiKeyScope};
+
+use super::routes::Routes;
+
+pub fn routes() -> Routes {
+    Routes::nest("/search").get(
+        "/",
+        controllers::search::search,
+        AuthPolicy::public().api_key(ApiKeyScope::PostsRead),
+    )
+}
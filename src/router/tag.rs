@@ -0,0 +1,17 @@
+use crate::{controllers, core::layers::auth_layer::AuthPolicy, models::ApiKeyScope};
+
+use super::routes::Routes;
+
+pub fn routes() -> Routes {
+    Routes::nest("/tags")
+        .get(
+            "/trending",
+            controllers::tag::get_trending_tags,
+            AuthPolicy::public().api_key(ApiKeyScope::PostsRead),
+        )
+        .get(
+            "/{tag}/posts",
+            controllers::post::find_tag_posts,
+            AuthPolicy::public().api_key(ApiKeyScope::PostsRead),
+        )
+}
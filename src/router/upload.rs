@@ -1,11 +1,19 @@
-use crate::{app_state::SharedAppState, controllers};
-use axum::{Router, routing::post};
+use tower_http::services::ServeDir;
 
-pub fn router() -> Router<SharedAppState> {
-    let router = Router::new();
+use crate::{constants, controllers, core::layers::auth_layer::AuthPolicy, models::ApiKeyScope};
 
-    router.nest(
-        "/upload",
-        Router::new().route("/", post(controllers::upload::upload_file)),
-    )
+use super::routes::Routes;
+
+pub fn routes() -> Routes {
+    Routes::nest("/upload")
+        .post(
+            "/",
+            controllers::upload::upload_file,
+            AuthPolicy::public().api_key(ApiKeyScope::UploadsWrite),
+        )
+        .merge(Routes::new().nest_service(
+            "/uploads",
+            ServeDir::new(constants::DISK_STORAGE_PATH.to_string()),
+            AuthPolicy::public(),
+        ))
 }
@@ -1,15 +1,97 @@
-use axum::{
-    Router,
-    routing::{get, patch},
-};
+use crate::{controllers, core::layers::auth_layer::AuthPolicy, models::ApiKeyScope};
 
-use crate::{app_state::SharedAppState, controllers};
+use super::routes::Routes;
 
-pub fn router() -> Router<SharedAppState> {
-    Router::new().nest(
-        "/user",
-        Router::new()
-            .route("/whoami", get(controllers::user::whoami))
-            .route("/update_profile", patch(controllers::user::update_profile)),
-    )
+pub fn routes() -> Routes {
+    Routes::nest("/user")
+        .delete(
+            "/",
+            controllers::account::delete_account,
+            AuthPolicy::authenticated(),
+        )
+        .post(
+            "/restore",
+            controllers::account::restore_account,
+            AuthPolicy::authenticated(),
+        )
+        .get(
+            "/export",
+            controllers::account::export_account,
+            AuthPolicy::authenticated(),
+        )
+        .get(
+            "/whoami",
+            controllers::user::whoami,
+            AuthPolicy::authenticated().api_key(ApiKeyScope::ProfileRead),
+        )
+        .patch(
+            "/update_profile",
+            controllers::user::update_profile,
+            AuthPolicy::authenticated().api_key(ApiKeyScope::ProfileWrite),
+        )
+        .post(
+            "/email",
+            controllers::user::request_email_change,
+            AuthPolicy::authenticated(),
+        )
+        .post(
+            "/email/confirm",
+            controllers::user::confirm_email_change,
+            AuthPolicy::authenticated(),
+        )
+        .get(
+            "/sessions",
+            controllers::user::get_sessions,
+            AuthPolicy::authenticated(),
+        )
+        .delete(
+            "/sessions",
+            controllers::user::revoke_other_sessions,
+            AuthPolicy::authenticated(),
+        )
+        .delete(
+            "/sessions/{session_id}",
+            controllers::user::revoke_session,
+            AuthPolicy::authenticated(),
+        )
+        .get(
+            "/api-keys",
+            controllers::api_key::get_api_keys,
+            AuthPolicy::authenticated(),
+        )
+        .post(
+            "/api-keys",
+            controllers::api_key::create_api_key,
+            AuthPolicy::authenticated(),
+        )
+        .delete(
+            "/api-keys/{api_key_id}",
+            controllers::api_key::revoke_api_key,
+            AuthPolicy::authenticated(),
+        )
+        .get(
+            "/2fa",
+            controllers::two_factor::get_two_factor_status,
+            AuthPolicy::authenticated(),
+        )
+        .post(
+            "/2fa/totp",
+            controllers::two_factor::enroll_totp,
+            AuthPolicy::authenticated(),
+        )
+        .post(
+            "/2fa/totp/confirm",
+            controllers::two_factor::confirm_totp,
+            AuthPolicy::authenticated(),
+        )
+        .post(
+            "/2fa/totp/disable",
+            controllers::two_factor::disable_totp,
+            AuthPolicy::authenticated(),
+        )
+        .post(
+            "/2fa/recovery-codes",
+            controllers::two_factor::regenerate_recovery_codes,
+            AuthPolicy::authenticated(),
+        )
 }
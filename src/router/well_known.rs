@@ -0,0 +1,11 @@
+use crate::{controllers, core::layers::auth_layer::AuthPolicy};
+
+use super::routes::Routes;
+
+pub fn routes() -> Routes {
+    Routes::nest("/.well-known").get(
+        "/jwks.json",
+        controllers::well_known::jwks,
+        AuthPolicy::public(),
+    )
+}
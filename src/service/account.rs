@@ -0,0 +1,181 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Result};
+
+use crate::{
+    models::{Post, PostComment, PostDetails, PostLike, User},
+    service,
+};
+
+/// Everything stored about a user, as handed out by the data export.
+pub struct AccountExport {
+    pub user: User,
+    pub posts: Vec<PostDetails>,
+    pub comments: Vec<PostComment>,
+    pub likes: Vec<PostLike>,
+}
+
+pub async fn schedule_account_deletion(
+    pool: &PgPool,
+    user_id: &str,
+    scheduled_at: DateTime<Utc>,
+) -> Result<User> {
+    sqlx::query_as(
+        r#"
+        UPDATE users
+        SET deletion_scheduled_at = COALESCE(deletion_scheduled_at, $2)
+        WHERE id = $1
+        RETURNING *
+    "#,
+    )
+    .bind(user_id)
+    .bind(scheduled_at)
+    .fetch_one(pool)
+    .await
+}
+
+/// Returns `None` if no deletion was scheduled for the user.
+pub async fn cancel_account_deletion(pool: &PgPool, user_id: &str) -> Result<Option<User>> {
+    sqlx::query_as(
+        r#"
+        UPDATE users
+        SET deletion_scheduled_at = NULL
+        WHERE id = $1 AND deletion_scheduled_at IS NOT NULL
+        RETURNING *
+    "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn get_accounts_due_for_purge(pool: &PgPool, limit: i64) -> Result<Vec<String>> {
+    sqlx::query_scalar(
+        r#"
+        SELECT id FROM users
+        WHERE deletion_scheduled_at <= NOW() AND deleted_at IS NULL
+        ORDER BY deletion_scheduled_at
+        LIMIT $1
+    "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Removes the user's posts, likes, follows, mentions and notifications, anonymizes
+/// their comments and messages so replies from other users stay readable, and strips
+/// the account of anything identifying. Notifications only the user acted on go away
+/// with them.
+///
+/// The `users` row is kept as a tombstone comments and messages keep pointing to.
+pub async fn purge_account(pool: &PgPool, user_id: &str) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    let email: Option<String> = sqlx::query_scalar(
+        r#"SELECT email FROM users WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"#,
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let email = match email {
+        Some(email) => email,
+        None => return Ok(()),
+    };
+
+    let statements = [
+        r#"DELETE FROM posts WHERE user_id = $1"#,
+        r#"DELETE FROM post_likes WHERE user_id = $1"#,
+        r#"DELETE FROM mentions m USING post_comments pc WHERE pc.id = m.comment_id AND pc.user_id = $1"#,
+        r#"DELETE FROM mentions WHERE user_id = $1"#,
+        r#"
+        UPDATE post_comments
+        SET content = '', deleted_at = COALESCE(deleted_at, NOW())
+        WHERE user_id = $1
+        "#,
+        r#"DELETE FROM message_attachments ma USING messages m WHERE m.id = ma.message_id AND m.user_id = $1"#,
+        r#"
+        UPDATE messages
+        SET content = '', deleted_at = COALESCE(deleted_at, NOW())
+        WHERE user_id = $1
+        "#,
+        r#"DELETE FROM follows WHERE follower_id = $1 OR followee_id = $1"#,
+        r#"DELETE FROM notifications WHERE user_id = $1"#,
+        r#"
+        WITH removed AS (
+            DELETE FROM notification_actors WHERE actor_id = $1 RETURNING notification_id
+        )
+        DELETE FROM notifications n
+        WHERE n.id IN (SELECT notification_id FROM removed)
+            AND NOT EXISTS (
+                SELECT 1 FROM notification_actors na
+                WHERE na.notification_id = n.id AND na.actor_id <> $1
+            )
+        "#,
+        r#"DELETE FROM notification_preferences WHERE user_id = $1"#,
+        r#"DELETE FROM digest_subscriptions WHERE user_id = $1"#,
+        r#"DELETE FROM sessions WHERE user_id = $1"#,
+        r#"DELETE FROM api_keys WHERE user_id = $1"#,
+        r#"DELETE FROM totp_credentials WHERE user_id = $1"#,
+        r#"DELETE FROM recovery_codes WHERE user_id = $1"#,
+        r#"DELETE FROM two_factor_challenges WHERE user_id = $1"#,
+        r#"DELETE FROM email_change_requests WHERE user_id = $1"#,
+        r#"
+        UPDATE users
+        SET
+            email = concat('deleted_', id, '@deleted.invalid'),
+            username = concat('deleted_', id),
+            profile_image_url = NULL,
+            is_verified = FALSE,
+            deletion_scheduled_at = NULL,
+            deleted_at = NOW()
+        WHERE id = $1
+        "#,
+    ];
+
+    for statement in statements {
+        sqlx::query(statement)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query(r#"DELETE FROM verification_pins WHERE email = $1"#)
+        .bind(email)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await
+}
+
+pub async fn get_account_export(pool: &PgPool, user_id: &str) -> Result<Option<AccountExport>> {
+    let user = match service::user::get_user_by_id(pool, user_id).await? {
+        Some(user) => user,
+        None => return Ok(None),
+    };
+
+    let (posts, comments, likes) = tokio::try_join!(
+        sqlx::query_as::<_, Post>(r#"SELECT * FROM posts WHERE user_id = $1 ORDER BY created_at"#)
+            .bind(user_id)
+            .fetch_all(pool),
+        sqlx::query_as::<_, PostComment>(
+            r#"SELECT * FROM post_comments WHERE user_id = $1 ORDER BY created_at"#
+        )
+        .bind(user_id)
+        .fetch_all(pool),
+        sqlx::query_as::<_, PostLike>(
+            r#"SELECT * FROM post_likes WHERE user_id = $1 ORDER BY created_at"#
+        )
+        .bind(user_id)
+        .fetch_all(pool),
+    )?;
+
+    let posts = service::post::get_post_details(pool, posts, None).await?;
+
+    Ok(Some(AccountExport {
+        user,
+        posts,
+        comments,
+        likes,
+    }))
+}
@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Result};
+
+use crate::models::{ApiKey, ApiKeyScope};
+
+pub async fn create_api_key(
+    pool: &PgPool,
+    user_id: &str,
+    name: &str,
+    token_prefix: &str,
+    token_hash: &str,
+    scopes: &[ApiKeyScope],
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<ApiKey> {
+    let scopes: Vec<&str> = scopes.iter().map(|s| s.to_str()).collect();
+
+    let api_key: ApiKey = sqlx::query_as(
+        r#"
+        INSERT INTO api_keys (user_id, name, token_prefix, token_hash, scopes, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+    "#,
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(token_prefix)
+    .bind(token_hash)
+    .bind(scopes)
+    .bind(expires_at)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(api_key)
+}
+
+pub async fn get_user_api_keys(pool: &PgPool, user_id: &str) -> Result<Vec<ApiKey>> {
+    sqlx::query_as(
+        r#"
+        SELECT * FROM api_keys
+        WHERE user_id = $1 AND revoked_at IS NULL
+        ORDER BY created_at DESC
+    "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Returns the api key matching the hash if it is neither revoked nor expired,
+/// bumping its `last_used_at` at most once a minute.
+pub async fn touch_active_api_key(pool: &PgPool, token_hash: &str) -> Result<Option<ApiKey>> {
+    sqlx::query_as(
+        r#"
+        WITH active AS (
+            SELECT * FROM api_keys
+            WHERE token_hash = $1
+                AND revoked_at IS NULL
+                AND (expires_at IS NULL OR expires_at > NOW())
+        ), touched AS (
+            UPDATE api_keys k
+            SET last_used_at = NOW()
+            FROM active
+            WHERE k.id = active.id
+                AND (active.last_used_at IS NULL OR active.last_used_at < NOW() - INTERVAL '1 minute')
+        )
+        SELECT * FROM active
+    "#,
+    )
+    .bind(token_hash)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn revoke_api_key(
+    pool: &PgPool,
+    user_id: &str,
+    api_key_id: &str,
+) -> Result<Option<String>> {
+    sqlx::query_scalar(
+        r#"
+        UPDATE api_keys
+        SET revoked_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+        RETURNING id
+    "#,
+    )
+    .bind(api_key_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
@@ -1,46 +1,43 @@
 use std::collections::HashMap;
 
-use sqlx::{PgPool, QueryBuilder, Result};
+use sqlx::{PgPool, Postgres, QueryBuilder, Result, Transaction};
 
+use crate::core::extractors::current_user::CurrentUser;
+use crate::core::utils::pagination;
 use crate::dtos::comment::{CreateCommentDto, UpdateCommentDto};
-use crate::models::{PostComment, PostCommentDetails, User};
-use crate::service;
+use crate::models::{Author, NotificationKind, PostComment, PostCommentDetails, User};
+use crate::service::{self, mention::MentionTarget};
+use crate::types::Page;
 
 pub async fn get_posts_comments(
     pool: &PgPool,
     post_id: &str,
-    offset: i64,
+    page: &Page,
     limit: i64,
     parent_id: Option<String>,
 ) -> Result<Vec<PostCommentDetails>> {
     let mut query_builder = QueryBuilder::new(
         r#"
-        SELECT * FROM post_comments
+        SELECT c.* FROM post_comments c
         WHERE"#,
     );
 
-    query_builder.push(" post_id = ");
+    query_builder.push(" c.post_id = ");
     query_builder.push_bind(post_id);
 
-    query_builder.push(" AND deleted_at IS NULL");
+    query_builder.push(" AND c.deleted_at IS NULL");
 
     match parent_id {
         Some(parent_id) => {
-            query_builder.push(" AND parent_id = ");
+            query_builder.push(" AND c.parent_id = ");
             query_builder.push_bind(parent_id);
         }
         None => {
-            query_builder.push(" AND parent_id IS NULL");
+            query_builder.push(" AND c.parent_id IS NULL");
         }
     }
 
-    query_builder.push(" ORDER BY created_at DESC");
-
-    query_builder.push(" OFFSET ");
-    query_builder.push_bind(offset);
-
-    query_builder.push(" LIMIT ");
-    query_builder.push_bind(limit);
+    pagination::push_page(&mut query_builder, "c", page, limit);
 
     let query = query_builder.build_query_as();
 
@@ -54,34 +51,62 @@ pub async fn get_posts_comments(
 pub async fn get_user_comments(
     pool: &PgPool,
     user_id: &str,
-    offset: i64,
+    page: &Page,
     limit: i64,
 ) -> Result<Vec<PostCommentDetails>> {
-    let comments: Vec<PostComment> = sqlx::query_as(
+    let mut query_builder = QueryBuilder::new(
         r#"
-        SELECT * FROM post_comments
-        WHERE user_id = $1 AND deleted_at IS NULL
-        ORDER BY created_at DESC
-        OFFSET $2 
-        LIMIT $3
-    "#,
-    )
-    .bind(user_id)
-    .bind(offset)
-    .bind(limit)
-    .fetch_all(pool)
-    .await?;
+        SELECT c.* FROM post_comments c
+        WHERE c.deleted_at IS NULL AND c.user_id = "#,
+    );
+
+    query_builder.push_bind(user_id);
+
+    pagination::push_page(&mut query_builder, "c", page, limit);
+
+    let comments: Vec<PostComment> = query_builder.build_query_as().fetch_all(pool).await?;
 
     let comment_details = get_comment_details(pool, comments).await?;
 
     Ok(comment_details)
 }
 
+pub async fn count_posts_comments(
+    pool: &PgPool,
+    post_id: &str,
+    parent_id: Option<&str>,
+) -> Result<i64> {
+    sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM post_comments
+        WHERE post_id = $1 AND deleted_at IS NULL AND parent_id IS NOT DISTINCT FROM $2
+        "#,
+    )
+    .bind(post_id)
+    .bind(parent_id)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn count_user_comments(pool: &PgPool, user_id: &str) -> Result<i64> {
+    sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM post_comments
+        WHERE user_id = $1 AND deleted_at IS NULL
+        "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
 pub async fn create_comment(
     pool: &PgPool,
     user_id: &str,
     body: CreateCommentDto,
 ) -> Result<PostComment> {
+    let mut tx = pool.begin().await?;
+
     let comment: PostComment = sqlx::query_as(
         r#"
         INSERT INTO post_comments (post_id, user_id, content, parent_id)
@@ -93,9 +118,33 @@ pub async fn create_comment(
     .bind(user_id)
     .bind(&body.content)
     .bind(body.parent_id)
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
 
+    notify_new_mentions(&mut tx, &comment).await?;
+
+    if let Some(parent_id) = &comment.parent_id {
+        let parent_author_id: Option<String> =
+            sqlx::query_scalar(r#"SELECT user_id FROM post_comments WHERE id = $1"#)
+                .bind(parent_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+        if let Some(parent_author_id) = parent_author_id {
+            service::notification::notify(
+                &mut *tx,
+                NotificationKind::Reply,
+                &comment.user_id,
+                &[parent_author_id],
+                Some(&comment.post_id),
+                Some(parent_id),
+            )
+            .await?;
+        }
+    }
+
+    tx.commit().await?;
+
     Ok(comment)
 }
 
@@ -105,6 +154,8 @@ pub async fn update_comment(
     comment_id: &str,
     body: UpdateCommentDto,
 ) -> Result<PostComment> {
+    let mut tx = pool.begin().await?;
+
     let comment: PostComment = sqlx::query_as(
         r#"
         UPDATE post_comments
@@ -116,36 +167,68 @@ pub async fn update_comment(
     .bind(&body.content)
     .bind(comment_id)
     .bind(user_id)
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
 
+    notify_new_mentions(&mut tx, &comment).await?;
+
+    tx.commit().await?;
+
     Ok(comment)
 }
 
-pub async fn delete_comment(pool: &PgPool, user_id: &str, comment_id: &str) -> Result<String> {
+pub async fn delete_comment(
+    pool: &PgPool,
+    actor: &CurrentUser,
+    comment_id: &str,
+) -> Result<String> {
     let comment_id: String = sqlx::query_scalar(
         r#"
         UPDATE post_comments
         SET deleted_at = NOW()
-        WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+        WHERE id = $1 AND (user_id = $2 OR $3) AND deleted_at IS NULL
         RETURNING id
         "#,
     )
     .bind(comment_id)
-    .bind(user_id)
+    .bind(&actor.id)
+    .bind(actor.can_moderate())
     .fetch_one(pool)
     .await?;
 
     Ok(comment_id)
 }
 
+async fn notify_new_mentions(
+    tx: &mut Transaction<'_, Postgres>,
+    comment: &PostComment,
+) -> Result<()> {
+    let mentioned_user_ids =
+        service::mention::sync_mentions(tx, MentionTarget::Comment(&comment.id), &comment.content)
+            .await?;
+
+    service::notification::notify(
+        &mut **tx,
+        NotificationKind::Mention,
+        &comment.user_id,
+        &mentioned_user_ids,
+        Some(&comment.post_id),
+        Some(&comment.id),
+    )
+    .await
+}
+
 async fn get_comment_details(
     pool: &PgPool,
     comments: Vec<PostComment>,
 ) -> Result<Vec<PostCommentDetails>> {
+    let comment_ids: Vec<String> = comments.iter().map(|c| c.id.clone()).collect();
     let user_ids: Vec<String> = comments.iter().map(|c| c.user_id.clone()).collect();
 
-    let users_by_id_map = get_users_by_id_map(pool, user_ids).await?;
+    let (users_by_id_map, mut mentions_by_comment) = tokio::try_join!(
+        get_users_by_id_map(pool, user_ids),
+        service::mention::get_mentions_by_target_map(pool, &comment_ids),
+    )?;
 
     let comment_details = comments
         .into_iter()
@@ -154,7 +237,12 @@ async fn get_comment_details(
                 .get(&comment.user_id)
                 .cloned()
                 .expect("[get_comment_details] author not found");
-            PostCommentDetails { comment, author }
+            let mentions = mentions_by_comment.remove(&comment.id).unwrap_or_default();
+            PostCommentDetails {
+                comment,
+                author,
+                mentions,
+            }
         })
         .collect();
 
@@ -164,12 +252,12 @@ async fn get_comment_details(
 async fn get_users_by_id_map(
     pool: &PgPool,
     user_ids: Vec<String>,
-) -> Result<HashMap<String, User>> {
+) -> Result<HashMap<String, Author>> {
     let users: Vec<User> = service::user::get_users_by_ids(pool, &user_ids).await?;
 
     let user_by_id_map = users
         .into_iter()
-        .map(|user| (user.id.clone(), user))
+        .map(|user| (user.id.clone(), Author::from(user)))
         .collect();
 
     Ok(user_by_id_map)
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Result};
+
+use crate::{
+    models::{Conversation, ConversationDetails, ConversationParticipant, Message, MessageDetails},
+    service,
+};
+
+/// Conversations of the user bound to `$1`, with how many messages they haven't read.
+const USER_CONVERSATIONS: &str = r#"
+    SELECT c.id, c.title, c.is_group, c.created_by, c.created_at, c.last_message_at,
+        (
+            SELECT COUNT(*) FROM messages m
+            WHERE m.conversation_id = c.id
+                AND m.user_id <> cp.user_id
+                AND m.deleted_at IS NULL
+                AND (cp.last_read_at IS NULL OR m.created_at > cp.last_read_at)
+        ) AS unread_count
+    FROM conversations c
+    JOIN conversation_participants cp ON cp.conversation_id = c.id AND cp.user_id = $1"#;
+
+/// Starts a conversation of the user with `participant_ids`, the other users. A pair
+/// of users only has one one-to-one conversation, starting it again returns the
+/// existing one. Fails with `RowNotFound` when a participant doesn't exist.
+pub async fn create_conversation(
+    pool: &PgPool,
+    user_id: &str,
+    participant_ids: &[String],
+    title: Option<&str>,
+) -> Result<String> {
+    let mut tx = pool.begin().await?;
+
+    let existing: i64 = sqlx::query_scalar(
+        r#"SELECT COUNT(*) FROM users WHERE id = ANY($1) AND deleted_at IS NULL"#,
+    )
+    .bind(participant_ids)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if existing as usize != participant_ids.len() {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    let conversation_id: String = match participant_ids {
+        [other_id] => {
+            let mut pair = [user_id, other_id.as_str()];
+            pair.sort();
+
+            sqlx::query_scalar(
+                r#"
+                INSERT INTO conversations (direct_key, created_by)
+                VALUES ($1, $2)
+                ON CONFLICT (direct_key) DO UPDATE SET direct_key = EXCLUDED.direct_key
+                RETURNING id
+            "#,
+            )
+            .bind(pair.join(":"))
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await?
+        }
+        _ => {
+            sqlx::query_scalar(
+                r#"
+                INSERT INTO conversations (title, is_group, created_by)
+                VALUES ($1, TRUE, $2)
+                RETURNING id
+            "#,
+            )
+            .bind(title)
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await?
+        }
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO conversation_participants (conversation_id, user_id)
+        SELECT $1, UNNEST($2::VARCHAR[])
+        ON CONFLICT (conversation_id, user_id) DO NOTHING
+    "#,
+    )
+    .bind(&conversation_id)
+    .bind(
+        participant_ids
+            .iter()
+            .map(String::as_str)
+            .chain([user_id])
+            .collect::<Vec<_>>(),
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(conversation_id)
+}
+
+/// Conversations of the user, latest message first.
+pub async fn get_conversations(
+    pool: &PgPool,
+    user_id: &str,
+    offset: i64,
+    limit: i64,
+) -> Result<Vec<ConversationDetails>> {
+    let conversations: Vec<Conversation> = sqlx::query_as(&format!(
+        r#"
+        {USER_CONVERSATIONS}
+        ORDER BY c.last_message_at DESC, c.id DESC
+        OFFSET $2
+        LIMIT $3
+    "#
+    ))
+    .bind(user_id)
+    .bind(offset)
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await?;
+
+    get_conversation_details(pool, conversations).await
+}
+
+pub async fn count_conversations(pool: &PgPool, user_id: &str) -> Result<i64> {
+    sqlx::query_scalar(r#"SELECT COUNT(*) FROM conversation_participants WHERE user_id = $1"#)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+}
+
+/// Returns `None` if the conversation doesn't exist or the user isn't part of it.
+pub async fn get_conversation(
+    pool: &PgPool,
+    user_id: &str,
+    conversation_id: &str,
+) -> Result<Option<ConversationDetails>> {
+    let conversation: Option<Conversation> =
+        sqlx::query_as(&format!("{USER_CONVERSATIONS} WHERE c.id = $2"))
+            .bind(user_id)
+            .bind(conversation_id)
+            .fetch_optional(pool)
+            .await?;
+
+    let Some(conversation) = conversation else {
+        return Ok(None);
+    };
+
+    let details = get_conversation_details(pool, vec![conversation]).await?;
+
+    Ok(details.into_iter().next())
+}
+
+pub async fn is_participant(pool: &PgPool, conversation_id: &str, user_id: &str) -> Result<bool> {
+    sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM conversation_participants
+            WHERE conversation_id = $1 AND user_id = $2
+        )
+    "#,
+    )
+    .bind(conversation_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Moves the read receipt of the user to now. Fails with `RowNotFound` when the
+/// user isn't part of the conversation.
+pub async fn mark_as_read(
+    pool: &PgPool,
+    conversation_id: &str,
+    user_id: &str,
+) -> Result<DateTime<Utc>> {
+    sqlx::query_scalar(
+        r#"
+        UPDATE conversation_participants
+        SET last_read_at = NOW()
+        WHERE conversation_id = $1 AND user_id = $2
+        RETURNING last_read_at
+    "#,
+    )
+    .bind(conversation_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+async fn get_conversation_details(
+    pool: &PgPool,
+    conversations: Vec<Conversation>,
+) -> Result<Vec<ConversationDetails>> {
+    let conversation_ids: Vec<String> = conversations.iter().map(|c| c.id.clone()).collect();
+
+    let (mut participants_by_conversation, mut last_message_by_conversation) = tokio::try_join!(
+        get_participants_by_conversation_map(pool, &conversation_ids),
+        get_last_message_by_conversation_map(pool, &conversation_ids),
+    )?;
+
+    let conversation_details = conversations
+        .into_iter()
+        .map(|conversation| ConversationDetails {
+            participants: participants_by_conversation
+                .remove(&conversation.id)
+                .unwrap_or_default(),
+            last_message: last_message_by_conversation.remove(&conversation.id),
+            conversation,
+        })
+        .collect();
+
+    Ok(conversation_details)
+}
+
+async fn get_participants_by_conversation_map(
+    pool: &PgPool,
+    conversation_ids: &[String],
+) -> Result<HashMap<String, Vec<ConversationParticipant>>> {
+    let participants: Vec<ConversationParticipant> = sqlx::query_as(
+        r#"
+        SELECT cp.conversation_id, u.id, u.username, u.profile_image_url, cp.last_read_at
+        FROM conversation_participants cp
+        JOIN users u ON u.id = cp.user_id
+        WHERE cp.conversation_id = ANY($1)
+        ORDER BY cp.joined_at, u.id
+    "#,
+    )
+    .bind(conversation_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let mut participants_by_conversation: HashMap<String, Vec<ConversationParticipant>> =
+        HashMap::new();
+
+    for participant in participants {
+        participants_by_conversation
+            .entry(participant.conversation_id.clone())
+            .or_default()
+            .push(participant);
+    }
+
+    Ok(participants_by_conversation)
+}
+
+async fn get_last_message_by_conversation_map(
+    pool: &PgPool,
+    conversation_ids: &[String],
+) -> Result<HashMap<String, MessageDetails>> {
+    let messages: Vec<Message> = sqlx::query_as(
+        r#"
+        SELECT DISTINCT ON (m.conversation_id) m.*
+        FROM messages m
+        WHERE m.conversation_id = ANY($1) AND m.deleted_at IS NULL
+        ORDER BY m.conversation_id, m.created_at DESC, m.id DESC
+    "#,
+    )
+    .bind(conversation_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let message_details = service::message::get_message_details(pool, messages).await?;
+
+    Ok(message_details
+        .into_iter()
+        .map(|details| (details.message.conversation_id.clone(), details))
+        .collect())
+}
@@ -0,0 +1,186 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Result};
+
+use crate::models::{EmailChangeRequest, User};
+
+pub enum EmailChange {
+    Changed { user: Box<User>, old_email: String },
+    EmailTaken,
+    Invalid,
+}
+
+pub async fn get_latest_email_change_request(
+    pool: &PgPool,
+    user_id: &str,
+) -> Result<Option<EmailChangeRequest>> {
+    sqlx::query_as(
+        r#"
+        SELECT * FROM email_change_requests
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        LIMIT 1
+    "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn get_active_email_change_request(
+    pool: &PgPool,
+    user_id: &str,
+) -> Result<Option<EmailChangeRequest>> {
+    sqlx::query_as(
+        r#"
+        SELECT * FROM email_change_requests
+        WHERE user_id = $1 AND invalidated_at IS NULL
+        ORDER BY created_at DESC
+        LIMIT 1
+    "#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Creates a new request for the user, invalidating any requests made before it.
+pub async fn create_email_change_request(
+    pool: &PgPool,
+    user_id: &str,
+    new_email: &str,
+    pin: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<EmailChangeRequest> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE email_change_requests
+        SET invalidated_at = NOW()
+        WHERE user_id = $1 AND invalidated_at IS NULL
+    "#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let request: EmailChangeRequest = sqlx::query_as(
+        r#"
+        INSERT INTO email_change_requests (user_id, new_email, pin, expires_at)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+    "#,
+    )
+    .bind(user_id)
+    .bind(new_email)
+    .bind(pin)
+    .bind(expires_at)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(request)
+}
+
+/// Atomically records a confirmation attempt against the request.
+///
+/// Returns `None` once the request has no attempts left, in which case it is invalidated.
+pub async fn register_email_change_attempt(
+    pool: &PgPool,
+    request_id: &str,
+    max_attempts: i32,
+) -> Result<Option<i32>> {
+    let attempts: Option<i32> = sqlx::query_scalar(
+        r#"
+        UPDATE email_change_requests
+        SET attempts = attempts + 1
+        WHERE id = $1 AND invalidated_at IS NULL AND attempts < $2
+        RETURNING attempts
+    "#,
+    )
+    .bind(request_id)
+    .bind(max_attempts)
+    .fetch_optional(pool)
+    .await?;
+
+    if attempts.is_none() {
+        sqlx::query(
+            r#"UPDATE email_change_requests SET invalidated_at = NOW() WHERE id = $1 AND invalidated_at IS NULL"#,
+        )
+        .bind(request_id)
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(attempts)
+}
+
+/// Swaps the user's email for the one in the request.
+///
+/// The request is consumed and pending login pins for both addresses are dropped in
+/// the same transaction, so the change either fully happens or not at all.
+pub async fn confirm_email_change(
+    pool: &PgPool,
+    user_id: &str,
+    request_id: &str,
+) -> Result<EmailChange> {
+    let mut tx = pool.begin().await?;
+
+    let new_email: Option<String> = sqlx::query_scalar(
+        r#"
+        UPDATE email_change_requests
+        SET invalidated_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND invalidated_at IS NULL
+        RETURNING new_email
+    "#,
+    )
+    .bind(request_id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let new_email = match new_email {
+        Some(new_email) => new_email,
+        None => return Ok(EmailChange::Invalid),
+    };
+
+    let old_email: String =
+        sqlx::query_scalar(r#"SELECT email FROM users WHERE id = $1 FOR UPDATE"#)
+            .bind(user_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+    let user: User = match sqlx::query_as(
+        r#"
+        UPDATE users
+        SET email = $2, updated_at = NOW()
+        WHERE id = $1
+        RETURNING *
+    "#,
+    )
+    .bind(user_id)
+    .bind(&new_email)
+    .fetch_one(&mut *tx)
+    .await
+    {
+        Ok(user) => user,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return Ok(EmailChange::EmailTaken);
+        }
+        Err(e) => return Err(e),
+    };
+
+    sqlx::query(r#"DELETE FROM verification_pins WHERE email = $1 OR email = $2"#)
+        .bind(&old_email)
+        .bind(&new_email)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(EmailChange::Changed {
+        user: Box::new(user),
+        old_email,
+    })
+}
@@ -0,0 +1,57 @@
+use sqlx::{PgPool, QueryBuilder, Result};
+
+use crate::{
+    core::utils::pagination,
+    models::{Post, PostDetails},
+    service,
+    types::Page,
+};
+
+/// Posts of the accounts the user follows plus their own, newest first.
+///
+/// Built on read from `follows`, relying on the `(user_id, created_at, id)` index
+/// of `posts`.
+pub async fn get_home_feed(
+    pool: &PgPool,
+    user_id: &str,
+    page: &Page,
+    limit: i64,
+) -> Result<Vec<PostDetails>> {
+    let mut query_builder = QueryBuilder::new(
+        r#"
+        SELECT p.* FROM posts p
+        JOIN users u ON u.id = p.user_id
+        WHERE p.deleted_at IS NULL
+            AND user_is_visible(u)
+            AND p.user_id IN (
+                SELECT followee_id FROM follows WHERE follower_id = "#,
+    );
+
+    query_builder.push_bind(user_id);
+    query_builder.push(" UNION ALL SELECT ");
+    query_builder.push_bind(user_id);
+    query_builder.push(")");
+
+    pagination::push_page(&mut query_builder, "p", page, limit);
+
+    let posts: Vec<Post> = query_builder.build_query_as().fetch_all(pool).await?;
+
+    service::post::get_post_details(pool, posts, Some(user_id)).await
+}
+
+pub async fn count_home_feed(pool: &PgPool, user_id: &str) -> Result<i64> {
+    sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM posts p
+        JOIN users u ON u.id = p.user_id
+        WHERE p.deleted_at IS NULL
+            AND user_is_visible(u)
+            AND p.user_id IN (
+                SELECT followee_id FROM follows WHERE follower_id = $1 UNION ALL SELECT $1
+            )
+    "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
@@ -0,0 +1,119 @@
+use sqlx::{PgPool, Result};
+
+use crate::{
+    models::{FollowListEntry, NotificationKind},
+    service,
+};
+
+/// Returns `false` if the user was already followed.
+pub async fn follow_user(pool: &PgPool, follower_id: &str, followee_id: &str) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO follows (follower_id, followee_id)
+        VALUES ($1, $2)
+        ON CONFLICT (follower_id, followee_id) DO NOTHING
+    "#,
+    )
+    .bind(follower_id)
+    .bind(followee_id)
+    .execute(pool)
+    .await?;
+
+    let followed = result.rows_affected() > 0;
+
+    if followed {
+        service::notification::notify(
+            pool,
+            NotificationKind::Follow,
+            follower_id,
+            &[followee_id.to_string()],
+            None,
+            None,
+        )
+        .await?;
+    }
+
+    Ok(followed)
+}
+
+/// Returns `false` if the user wasn't followed.
+pub async fn unfollow_user(pool: &PgPool, follower_id: &str, followee_id: &str) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        DELETE FROM follows
+        WHERE follower_id = $1 AND followee_id = $2
+    "#,
+    )
+    .bind(follower_id)
+    .bind(followee_id)
+    .execute(pool)
+    .await?;
+
+    let unfollowed = result.rows_affected() > 0;
+
+    if unfollowed {
+        service::notification::retract(
+            pool,
+            NotificationKind::Follow,
+            follower_id,
+            followee_id,
+            None,
+            None,
+        )
+        .await?;
+    }
+
+    Ok(unfollowed)
+}
+
+/// Followers of the user, deleted accounts left out.
+pub async fn get_followers(
+    pool: &PgPool,
+    user_id: &str,
+    offset: i64,
+    limit: i64,
+) -> Result<Vec<FollowListEntry>> {
+    sqlx::query_as(
+        r#"
+        SELECT u.id, u.username, u.profile_image_url, f.created_at AS followed_at
+        FROM follows f
+        JOIN users u ON u.id = f.follower_id
+        WHERE f.followee_id = $1
+            AND u.deleted_at IS NULL
+        ORDER BY f.created_at DESC, f.id DESC
+        OFFSET $2
+        LIMIT $3
+    "#,
+    )
+    .bind(user_id)
+    .bind(offset)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Users the user follows, deleted accounts left out.
+pub async fn get_following(
+    pool: &PgPool,
+    user_id: &str,
+    offset: i64,
+    limit: i64,
+) -> Result<Vec<FollowListEntry>> {
+    sqlx::query_as(
+        r#"
+        SELECT u.id, u.username, u.profile_image_url, f.created_at AS followed_at
+        FROM follows f
+        JOIN users u ON u.id = f.followee_id
+        WHERE f.follower_id = $1
+            AND u.deleted_at IS NULL
+        ORDER BY f.created_at DESC, f.id DESC
+        OFFSET $2
+        LIMIT $3
+    "#,
+    )
+    .bind(user_id)
+    .bind(offset)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
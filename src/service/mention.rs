@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use sqlx::{PgPool, Postgres, Result, Transaction};
+
+use crate::{core::utils::mention, models::Mention};
+
+#[derive(Clone, Copy)]
+pub enum MentionTarget<'a> {
+    Post(&'a str),
+    Comment(&'a str),
+}
+
+impl MentionTarget<'_> {
+    fn column(self) -> &'static str {
+        match self {
+            MentionTarget::Post(_) => "post_id",
+            MentionTarget::Comment(_) => "comment_id",
+        }
+    }
+
+    fn id(&self) -> &str {
+        match self {
+            MentionTarget::Post(id) | MentionTarget::Comment(id) => id,
+        }
+    }
+}
+
+/// Replaces the mentions of `target` by the ones of its new `content`, mentions of
+/// unknown or deleted users are dropped. Returns the users that were not already
+/// mentioned by it, so they are only notified once.
+pub async fn sync_mentions(
+    tx: &mut Transaction<'_, Postgres>,
+    target: MentionTarget<'_>,
+    content: &str,
+) -> Result<Vec<String>> {
+    let column = target.column();
+
+    let previous_user_ids: Vec<String> = sqlx::query_scalar(&format!(
+        "DELETE FROM mentions WHERE {column} = $1 RETURNING user_id"
+    ))
+    .bind(target.id())
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let candidates = mention::extract_mentions(content);
+
+    if candidates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let usernames: Vec<&str> = candidates.iter().map(|c| c.username.as_str()).collect();
+
+    let users: Vec<(String, String)> = sqlx::query_as(
+        r#"SELECT username, id FROM users WHERE username = ANY($1) AND deleted_at IS NULL"#,
+    )
+    .bind(&usernames)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let user_id_by_username: HashMap<String, String> = users.into_iter().collect();
+
+    let mut user_ids = Vec::new();
+    let mut starts = Vec::new();
+    let mut ends = Vec::new();
+
+    for candidate in candidates {
+        if let Some(user_id) = user_id_by_username.get(&candidate.username) {
+            user_ids.push(user_id.clone());
+            starts.push(candidate.start as i32);
+            ends.push(candidate.end as i32);
+        }
+    }
+
+    sqlx::query(&format!(
+        r#"
+        INSERT INTO mentions (user_id, {column}, start_offset, end_offset)
+        SELECT user_id, $1, start_offset, end_offset
+        FROM UNNEST($2::VARCHAR[], $3::INTEGER[], $4::INTEGER[])
+            AS m(user_id, start_offset, end_offset)
+    "#
+    ))
+    .bind(target.id())
+    .bind(&user_ids)
+    .bind(&starts)
+    .bind(&ends)
+    .execute(&mut **tx)
+    .await?;
+
+    user_ids.sort();
+    user_ids.dedup();
+    user_ids.retain(|user_id| !previous_user_ids.contains(user_id));
+
+    Ok(user_ids)
+}
+
+/// Mentions of the given posts or comments grouped by their id, in order of
+/// appearance.
+pub async fn get_mentions_by_target_map(
+    pool: &PgPool,
+    target_ids: &[String],
+) -> Result<HashMap<String, Vec<Mention>>> {
+    let mentions: Vec<Mention> = sqlx::query_as(
+        r#"
+        SELECT COALESCE(m.post_id, m.comment_id) AS target_id, m.user_id, u.username,
+            m.start_offset AS start, m.end_offset AS "end"
+        FROM mentions m
+        JOIN users u ON u.id = m.user_id
+        WHERE m.post_id = ANY($1) OR m.comment_id = ANY($1)
+        ORDER BY m.start_offset
+    "#,
+    )
+    .bind(target_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let mut mentions_by_target: HashMap<String, Vec<Mention>> = HashMap::new();
+
+    for mention in mentions {
+        mentions_by_target
+            .entry(mention.target_id.clone())
+            .or_default()
+            .push(mention);
+    }
+
+    Ok(mentions_by_target)
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+
+use sqlx::{PgPool, QueryBuilder, Result};
+
+use crate::{
+    core::utils::pagination,
+    dtos::conversation::{SendMessageDto, UpdateMessageDto},
+    models::{Message, MessageAttachment, MessageDetails},
+    types::Page,
+};
+
+/// History of the conversation, newest first.
+pub async fn get_messages(
+    pool: &PgPool,
+    conversation_id: &str,
+    page: &Page,
+    limit: i64,
+) -> Result<Vec<MessageDetails>> {
+    let mut query_builder =
+        QueryBuilder::new("SELECT m.* FROM messages m WHERE m.conversation_id = ");
+
+    query_builder.push_bind(conversation_id);
+
+    pagination::push_page(&mut query_builder, "m", page, limit);
+
+    let messages: Vec<Message> = query_builder.build_query_as().fetch_all(pool).await?;
+
+    get_message_details(pool, messages).await
+}
+
+pub async fn count_messages(pool: &PgPool, conversation_id: &str) -> Result<i64> {
+    sqlx::query_scalar(r#"SELECT COUNT(*) FROM messages WHERE conversation_id = $1"#)
+        .bind(conversation_id)
+        .fetch_one(pool)
+        .await
+}
+
+/// Fails with `RowNotFound` when the user isn't part of the conversation.
+pub async fn send_message(
+    pool: &PgPool,
+    user_id: &str,
+    conversation_id: &str,
+    body: SendMessageDto,
+) -> Result<MessageDetails> {
+    let mut tx = pool.begin().await?;
+
+    let message: Message = sqlx::query_as(
+        r#"
+        INSERT INTO messages (conversation_id, user_id, content)
+        SELECT $1, $2, $3
+        WHERE EXISTS (
+            SELECT 1 FROM conversation_participants
+            WHERE conversation_id = $1 AND user_id = $2
+        )
+        RETURNING *
+    "#,
+    )
+    .bind(conversation_id)
+    .bind(user_id)
+    .bind(&body.content)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let mut attachments = Vec::with_capacity(body.attachments.len());
+
+    for attachment in body.attachments {
+        let attachment: MessageAttachment = sqlx::query_as(
+            r#"
+            INSERT INTO message_attachments (message_id, media_url, media_type, mime_type, width, height, file_size)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+        "#,
+        )
+        .bind(&message.id)
+        .bind(&attachment.url)
+        .bind(attachment.r#type)
+        .bind(&attachment.mime_type)
+        .bind(attachment.width)
+        .bind(attachment.height)
+        .bind(attachment.size)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        attachments.push(attachment);
+    }
+
+    sqlx::query(
+        r#"
+        UPDATE conversations
+        SET last_message_at = GREATEST(last_message_at, $2)
+        WHERE id = $1
+    "#,
+    )
+    .bind(conversation_id)
+    .bind(message.created_at)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(MessageDetails {
+        message,
+        attachments,
+    })
+}
+
+/// Only the author can edit a message. Fails with `RowNotFound` otherwise, or when the
+/// message was deleted.
+pub async fn update_message(
+    pool: &PgPool,
+    user_id: &str,
+    conversation_id: &str,
+    message_id: &str,
+    body: UpdateMessageDto,
+) -> Result<MessageDetails> {
+    let message: Message = sqlx::query_as(
+        r#"
+        UPDATE messages
+        SET content = $1
+        WHERE id = $2 AND conversation_id = $3 AND user_id = $4 AND deleted_at IS NULL
+        RETURNING *
+    "#,
+    )
+    .bind(&body.content)
+    .bind(message_id)
+    .bind(conversation_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await?;
+
+    let details = get_message_details(pool, vec![message]).await?;
+
+    details.into_iter().next().ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Clears the content of a message of the user and hides its attachments.
+pub async fn delete_message(
+    pool: &PgPool,
+    user_id: &str,
+    conversation_id: &str,
+    message_id: &str,
+) -> Result<String> {
+    sqlx::query_scalar(
+        r#"
+        UPDATE messages
+        SET content = '', deleted_at = NOW()
+        WHERE id = $1 AND conversation_id = $2 AND user_id = $3 AND deleted_at IS NULL
+        RETURNING id
+    "#,
+    )
+    .bind(message_id)
+    .bind(conversation_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn get_message_details(
+    pool: &PgPool,
+    messages: Vec<Message>,
+) -> Result<Vec<MessageDetails>> {
+    let message_ids: Vec<String> = messages
+        .iter()
+        .filter(|m| m.deleted_at.is_none())
+        .map(|m| m.id.clone())
+        .collect();
+
+    let mut attachments_by_message = get_attachments_by_message_map(pool, &message_ids).await?;
+
+    let message_details = messages
+        .into_iter()
+        .map(|message| MessageDetails {
+            attachments: attachments_by_message
+                .remove(&message.id)
+                .unwrap_or_default(),
+            message,
+        })
+        .collect();
+
+    Ok(message_details)
+}
+
+async fn get_attachments_by_message_map(
+    pool: &PgPool,
+    message_ids: &[String],
+) -> Result<HashMap<String, Vec<MessageAttachment>>> {
+    let attachments: Vec<MessageAttachment> = sqlx::query_as(
+        r#"
+        SELECT * FROM message_attachments
+        WHERE message_id = ANY($1)
+        ORDER BY created_at, id
+    "#,
+    )
+    .bind(message_ids)
+    .fetch_all(pool)
+    .await?;
+
+    let mut attachments_by_message: HashMap<String, Vec<MessageAttachment>> = HashMap::new();
+
+    for attachment in attachments {
+        attachments_by_message
+            .entry(attachment.message_id.clone())
+            .or_default()
+            .push(attachment);
+    }
+
+    Ok(attachments_by_message)
+}
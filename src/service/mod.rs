@@ -1,4 +1,20 @@
+pub mod account;
+pub mod api_key;
 pub mod comment;
+pub mod conversation;
+pub mod email_change;
+pub mod feed;
+pub mod follow;
+pub mod mention;
+pub mod message;
+pub mod notification;
+pub mod notification_preference;
 pub mod post;
+pub mod profile;
+pub mod search;
+pub mod session;
+pub mod tag;
+pub mod two_factor;
+pub mod upload;
 pub mod user;
 pub mod verification_pin;
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool, Result};
+
+use crate::{
+    constants::NOTIFICATION_ACTORS_PREVIEW,
+    models::{
+        Notification, NotificationActor, NotificationDetails, NotificationKind,
+        PendingNotificationMail,
+    },
+};
+
+/// Notifies `user_ids` that `actor_id` did `kind` on the target. The event joins the
+/// unread notification of each user for the same kind and target when there is one.
+/// The actor is never notified about itself, nor users who turned the kind off. New
+/// notifications of a kind the user gets by email wait for the mail job.
+pub async fn notify<'e>(
+    executor: impl PgExecutor<'e>,
+    kind: NotificationKind,
+    actor_id: &str,
+    user_ids: &[String],
+    post_id: Option<&str>,
+    comment_id: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        WITH notified AS (
+            INSERT INTO notifications (user_id, kind, post_id, comment_id, email_pending)
+            SELECT DISTINCT r.user_id, $2, $4, $5, EXISTS (
+                SELECT 1 FROM notification_preferences np
+                WHERE np.user_id = r.user_id AND np.kind = $2 AND np.delivery = 'email'
+            )
+            FROM UNNEST($3::VARCHAR[]) AS r(user_id)
+            WHERE r.user_id <> $1
+                AND NOT EXISTS (
+                    SELECT 1 FROM notification_preferences np
+                    WHERE np.user_id = r.user_id AND np.kind = $2 AND np.delivery = 'none'
+                )
+            ON CONFLICT (user_id, kind, COALESCE(post_id, ''), COALESCE(comment_id, ''))
+                WHERE read_at IS NULL
+            DO UPDATE SET updated_at = NOW()
+            RETURNING id
+        )
+        INSERT INTO notification_actors (notification_id, actor_id)
+        SELECT id, $1 FROM notified
+        ON CONFLICT (notification_id, actor_id) DO UPDATE SET created_at = NOW()
+    "#,
+    )
+    .bind(actor_id)
+    .bind(kind)
+    .bind(user_ids)
+    .bind(post_id)
+    .bind(comment_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Takes back an event that was undone, such as an unlike, as long as the user hasn't
+/// read it. The notification goes away with its last actor.
+pub async fn retract<'e>(
+    executor: impl PgExecutor<'e>,
+    kind: NotificationKind,
+    actor_id: &str,
+    user_id: &str,
+    post_id: Option<&str>,
+    comment_id: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        WITH retracted AS (
+            DELETE FROM notification_actors na
+            USING notifications n
+            WHERE na.notification_id = n.id
+                AND na.actor_id = $1
+                AND n.user_id = $2
+                AND n.kind = $3
+                AND n.post_id IS NOT DISTINCT FROM $4
+                AND n.comment_id IS NOT DISTINCT FROM $5
+                AND n.read_at IS NULL
+            RETURNING na.notification_id
+        )
+        DELETE FROM notifications n
+        WHERE n.id IN (SELECT notification_id FROM retracted)
+            AND NOT EXISTS (
+                SELECT 1 FROM notification_actors na
+                WHERE na.notification_id = n.id AND na.actor_id <> $1
+            )
+    "#,
+    )
+    .bind(actor_id)
+    .bind(user_id)
+    .bind(kind)
+    .bind(post_id)
+    .bind(comment_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+const NOTIFICATION_COLUMNS: &str = r#"
+    SELECT n.id, n.kind, n.post_id, n.comment_id, n.read_at, n.created_at, n.updated_at,
+        (SELECT COUNT(*) FROM notification_actors na WHERE na.notification_id = n.id) AS actors_count
+    FROM notifications n"#;
+
+/// Notifications of the user, latest activity first.
+pub async fn get_notifications(
+    pool: &PgPool,
+    user_id: &str,
+    unread_only: bool,
+    offset: i64,
+    limit: i64,
+) -> Result<Vec<NotificationDetails>> {
+    let notifications: Vec<Notification> = sqlx::query_as(&format!(
+        r#"
+            {NOTIFICATION_COLUMNS}
+            WHERE n.user_id = $1 AND (NOT $2 OR n.read_at IS NULL)
+            ORDER BY n.updated_at DESC, n.id DESC
+            OFFSET $3
+            LIMIT $4
+        "#
+    ))
+    .bind(user_id)
+    .bind(unread_only)
+    .bind(offset)
+    // One extra row tells whether there is a next page
+    .bind(limit + 1)
+    .fetch_all(pool)
+    .await?;
+
+    get_notification_details(pool, notifications).await
+}
+
+pub async fn get_notifications_by_ids(
+    pool: &PgPool,
+    ids: &[String],
+) -> Result<Vec<NotificationDetails>> {
+    let notifications: Vec<Notification> =
+        sqlx::query_as(&format!("{NOTIFICATION_COLUMNS} WHERE n.id = ANY($1)"))
+            .bind(ids)
+            .fetch_all(pool)
+            .await?;
+
+    get_notification_details(pool, notifications).await
+}
+
+pub async fn count_notifications(pool: &PgPool, user_id: &str, unread_only: bool) -> Result<i64> {
+    sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM notifications
+        WHERE user_id = $1 AND (NOT $2 OR read_at IS NULL)
+    "#,
+    )
+    .bind(user_id)
+    .bind(unread_only)
+    .fetch_one(pool)
+    .await
+}
+
+/// Marks the given notifications of the user as read, or all of them without `ids`.
+/// Returns how many were unread.
+pub async fn mark_as_read(pool: &PgPool, user_id: &str, ids: Option<&[String]>) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE notifications
+        SET read_at = NOW()
+        WHERE user_id = $1 AND read_at IS NULL AND ($2::VARCHAR[] IS NULL OR id = ANY($2))
+    "#,
+    )
+    .bind(user_id)
+    .bind(ids)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Claims the unread notifications waiting to be emailed that haven't changed since
+/// `settled_before`, by marking them as emailed.
+/// Every server instance runs the mail job, a notification is only claimed by one.
+/// Notifications from before the user turned emails on are skipped.
+pub async fn claim_pending_notification_mails(
+    pool: &PgPool,
+    settled_before: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<PendingNotificationMail>> {
+    sqlx::query_as(
+        r#"
+        WITH claimed AS (
+            UPDATE notifications
+            SET email_pending = FALSE, emailed_at = NOW()
+            WHERE id IN (
+                SELECT n.id
+                FROM notifications n
+                JOIN notification_preferences np
+                    ON np.user_id = n.user_id AND np.kind = n.kind AND np.delivery = 'email'
+                JOIN users u ON u.id = n.user_id
+                WHERE n.email_pending
+                    AND n.read_at IS NULL
+                    AND n.updated_at <= $1
+                    AND n.created_at >= np.updated_at
+                    AND u.deleted_at IS NULL
+                ORDER BY n.updated_at
+                LIMIT $2
+                FOR UPDATE OF n SKIP LOCKED
+            )
+            RETURNING id, user_id
+        )
+        SELECT c.id AS notification_id, c.user_id, u.email
+        FROM claimed c
+        JOIN users u ON u.id = c.user_id
+    "#,
+    )
+    .bind(settled_before)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Gives back claimed notifications that couldn't be emailed, so they are tried again.
+pub async fn release_notification_mails(pool: &PgPool, ids: &[String]) -> Result<()> {
+    sqlx::query(
+        r#"UPDATE notifications SET email_pending = TRUE, emailed_at = NULL WHERE id = ANY($1)"#,
+    )
+    .bind(ids)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+async fn get_notification_details(
+    pool: &PgPool,
+    notifications: Vec<Notification>,
+) -> Result<Vec<NotificationDetails>> {
+    let notification_ids: Vec<String> = notifications.iter().map(|n| n.id.clone()).collect();
+
+    let mut actors_by_notification =
+        get_actors_by_notification_map(pool, &notification_ids).await?;
+
+    let notification_details = notifications
+        .into_iter()
+        .map(|notification| {
+            let actors = actors_by_notification
+                .remove(&notification.id)
+                .unwrap_or_default();
+            NotificationDetails::new(notification, actors)
+        })
+        .collect();
+
+    Ok(notification_details)
+}
+
+async fn get_actors_by_notification_map(
+    pool: &PgPool,
+    notification_ids: &[String],
+) -> Result<HashMap<String, Vec<NotificationActor>>> {
+    let actors: Vec<NotificationActor> = sqlx::query_as(
+        r#"
+        SELECT a.notification_id, u.id, u.username, u.profile_image_url
+        FROM (
+            SELECT na.notification_id, na.actor_id,
+                ROW_NUMBER() OVER (PARTITION BY na.notification_id ORDER BY na.created_at DESC) AS position
+            FROM notification_actors na
+            WHERE na.notification_id = ANY($1)
+        ) a
+        JOIN users u ON u.id = a.actor_id
+        WHERE a.position <= $2
+        ORDER BY a.notification_id, a.position
+    "#,
+    )
+    .bind(notification_ids)
+    .bind(NOTIFICATION_ACTORS_PREVIEW)
+    .fetch_all(pool)
+    .await?;
+
+    let mut actors_by_notification: HashMap<String, Vec<NotificationActor>> = HashMap::new();
+
+    for actor in actors {
+        actors_by_notification
+            .entry(actor.notification_id.clone())
+            .or_default()
+            .push(actor);
+    }
+
+    Ok(actors_by_notification)
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgExecutor, PgPool, Result};
+
+use crate::models::{
+    DigestActivity, DigestFrequency, DigestPostActivity, DueDigest, NotificationDelivery,
+    NotificationKind,
+};
+
+const DIGEST_TOP_POSTS: i64 = 10;
+
+/// Delivery of every notification kind for the user, in-app unless set otherwise.
+pub async fn get_deliveries(
+    pool: &PgPool,
+    user_id: &str,
+) -> Result<HashMap<NotificationKind, NotificationDelivery>> {
+    let rows: Vec<(NotificationKind, NotificationDelivery)> =
+        sqlx::query_as(r#"SELECT kind, delivery FROM notification_preferences WHERE user_id = $1"#)
+            .bind(user_id)
+            .fetch_all(pool)
+            .await?;
+
+    let mut deliveries: HashMap<NotificationKind, NotificationDelivery> = NotificationKind::ALL
+        .into_iter()
+        .map(|kind| (kind, NotificationDelivery::InApp))
+        .collect();
+
+    deliveries.extend(rows);
+
+    Ok(deliveries)
+}
+
+/// Only a change of delivery moves `updated_at`, so turning emails on doesn't mail
+/// notifications that piled up before. Turning them off drops the pending emails.
+pub async fn set_deliveries(
+    pool: &PgPool,
+    user_id: &str,
+    deliveries: &HashMap<NotificationKind, NotificationDelivery>,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    for (kind, delivery) in deliveries {
+        sqlx::query(
+            r#"
+            INSERT INTO notification_preferences (user_id, kind, delivery)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, kind) DO UPDATE
+            SET delivery = EXCLUDED.delivery, updated_at = NOW()
+            WHERE notification_preferences.delivery <> EXCLUDED.delivery
+        "#,
+        )
+        .bind(user_id)
+        .bind(kind)
+        .bind(delivery)
+        .execute(&mut *tx)
+        .await?;
+
+        if *delivery != NotificationDelivery::Email {
+            clear_pending_emails(&mut *tx, user_id, *kind).await?;
+        }
+    }
+
+    tx.commit().await
+}
+
+/// Keeps delivering the kind in-app when it was emailed, returns `false` otherwise.
+pub async fn disable_email_delivery(
+    pool: &PgPool,
+    user_id: &str,
+    kind: NotificationKind,
+) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        r#"
+        UPDATE notification_preferences
+        SET delivery = 'inapp', updated_at = NOW()
+        WHERE user_id = $1 AND kind = $2 AND delivery = 'email'
+    "#,
+    )
+    .bind(user_id)
+    .bind(kind)
+    .execute(&mut *tx)
+    .await?;
+
+    clear_pending_emails(&mut *tx, user_id, kind).await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+async fn clear_pending_emails<'e>(
+    executor: impl PgExecutor<'e>,
+    user_id: &str,
+    kind: NotificationKind,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE notifications
+        SET email_pending = FALSE
+        WHERE user_id = $1 AND kind = $2 AND email_pending
+    "#,
+    )
+    .bind(user_id)
+    .bind(kind)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_digest_frequency(pool: &PgPool, user_id: &str) -> Result<DigestFrequency> {
+    let frequency: Option<DigestFrequency> =
+        sqlx::query_scalar(r#"SELECT frequency FROM digest_subscriptions WHERE user_id = $1"#)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(frequency.unwrap_or(DigestFrequency::None))
+}
+
+/// Subscribing starts the period of the first digest, so it doesn't cover activity
+/// from before the user asked for it.
+pub async fn set_digest_frequency(
+    pool: &PgPool,
+    user_id: &str,
+    frequency: DigestFrequency,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO digest_subscriptions (user_id, frequency)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE
+        SET frequency = EXCLUDED.frequency,
+            last_sent_at = CASE
+                WHEN digest_subscriptions.frequency = 'none' THEN NOW()
+                ELSE digest_subscriptions.last_sent_at
+            END
+    "#,
+    )
+    .bind(user_id)
+    .bind(frequency)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Claims the digests that are due by starting their next period now. Every server
+/// instance runs the mail job, a digest is only claimed by one.
+pub async fn claim_due_digests(pool: &PgPool, limit: i64) -> Result<Vec<DueDigest>> {
+    sqlx::query_as(
+        r#"
+        WITH due AS (
+            SELECT ds.user_id, ds.last_sent_at
+            FROM digest_subscriptions ds
+            JOIN users u ON u.id = ds.user_id
+            WHERE (
+                    (ds.frequency = 'daily' AND ds.last_sent_at <= NOW() - INTERVAL '1 day')
+                    OR (ds.frequency = 'weekly' AND ds.last_sent_at <= NOW() - INTERVAL '7 days')
+                )
+                AND u.deleted_at IS NULL
+                AND u.status = 'active'
+            ORDER BY ds.last_sent_at
+            LIMIT $1
+            FOR UPDATE OF ds SKIP LOCKED
+        ), claimed AS (
+            UPDATE digest_subscriptions ds
+            SET last_sent_at = NOW()
+            FROM due
+            WHERE ds.user_id = due.user_id
+            RETURNING ds.user_id, ds.frequency, due.last_sent_at, ds.last_sent_at AS until
+        )
+        SELECT c.user_id, u.email, c.frequency, c.last_sent_at, c.until
+        FROM claimed c
+        JOIN users u ON u.id = c.user_id
+    "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Likes and comments others left on the user's posts, and new followers, between
+/// `since` and `until`. Posts with the most activity come first.
+pub async fn get_digest_activity(
+    pool: &PgPool,
+    user_id: &str,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<DigestActivity> {
+    let posts: Vec<DigestPostActivity> = sqlx::query_as(
+        r#"
+        SELECT title, likes_count, comments_count
+        FROM (
+            SELECT p.title, p.created_at,
+                (
+                    SELECT COUNT(*) FROM post_likes pl
+                    WHERE pl.post_id = p.id AND pl.user_id <> $1
+                        AND pl.created_at > $2 AND pl.created_at <= $3
+                ) AS likes_count,
+                (
+                    SELECT COUNT(*) FROM post_comments pc
+                    WHERE pc.post_id = p.id AND pc.user_id <> $1 AND pc.deleted_at IS NULL
+                        AND pc.created_at > $2 AND pc.created_at <= $3
+                ) AS comments_count
+            FROM posts p
+            WHERE p.user_id = $1 AND p.deleted_at IS NULL
+        ) activity
+        WHERE likes_count + comments_count > 0
+        ORDER BY likes_count + comments_count DESC, created_at DESC
+        LIMIT $4
+    "#,
+    )
+    .bind(user_id)
+    .bind(since)
+    .bind(until)
+    .bind(DIGEST_TOP_POSTS)
+    .fetch_all(pool)
+    .await?;
+
+    let followers_count: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM follows
+        WHERE followee_id = $1 AND created_at > $2 AND created_at <= $3
+    "#,
+    )
+    .bind(user_id)
+    .bind(since)
+    .bind(until)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(DigestActivity {
+        posts,
+        followers_count,
+    })
+}
+
+/// Gives back a claimed digest that couldn't be sent, so it is tried again. Left alone
+/// when the subscription changed since.
+pub async fn release_digest(pool: &PgPool, digest: &DueDigest) -> Result<()> {
+    sqlx::query(
+        r#"
+        UPDATE digest_subscriptions
+        SET last_sent_at = $2
+        WHERE user_id = $1 AND last_sent_at = $3
+    "#,
+    )
+    .bind(&digest.user_id)
+    .bind(digest.last_sent_at)
+    .bind(digest.until)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
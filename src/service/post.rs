@@ -1,11 +1,20 @@
-use std::{collections::HashMap, sync::Arc, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Instant,
+};
 
-use sqlx::{PgPool, Result};
+use sqlx::{PgPool, QueryBuilder, Result};
 
 use crate::{
+    core::{
+        extractors::current_user::CurrentUser,
+        utils::{hashtag, pagination},
+    },
     dtos::post::{CreatePostDto, UpdatePostDto},
-    models::{Post, PostDetails, PostMedia, User},
-    service,
+    models::{Author, NotificationKind, Post, PostDetails, PostMedia, PostViewerContext, User},
+    service::{self, mention::MentionTarget},
+    types::Page,
 };
 
 pub async fn create_post(
@@ -13,6 +22,8 @@ pub async fn create_post(
     user_id: &str,
     body: CreatePostDto,
 ) -> Result<(Post, Vec<PostMedia>), String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
     let post: Post = sqlx::query_as(
         r#"
         INSERT INTO posts (user_id, title, content)
@@ -23,10 +34,32 @@ pub async fn create_post(
     .bind(user_id)
     .bind(&body.title)
     .bind(&body.content)
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await
     .map_err(|e| e.to_string())?;
 
+    service::tag::sync_post_tags(&mut tx, &post.id, &hashtag::extract_hashtags(&post.content))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mentioned_user_ids =
+        service::mention::sync_mentions(&mut tx, MentionTarget::Post(&post.id), &post.content)
+            .await
+            .map_err(|e| e.to_string())?;
+
+    service::notification::notify(
+        &mut *tx,
+        NotificationKind::Mention,
+        user_id,
+        &mentioned_user_ids,
+        Some(&post.id),
+        None,
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+
     let mut handles = Vec::new();
 
     let pool = Arc::new(pool.clone());
@@ -42,11 +75,11 @@ pub async fn create_post(
             "#)
             .bind(&post_id)
             .bind(&media.url)
-            .bind(&media.r#type.to_str())
+            .bind(media.r#type.to_str())
             .bind(&media.mime_type)
-            .bind(&media.width)
-            .bind(&media.height)
-            .bind(&media.size)
+            .bind(media.width)
+            .bind(media.height)
+            .bind(media.size)
             .fetch_one(&*pool)
             .await
             .map_err(|e| e.to_string())?;
@@ -66,23 +99,31 @@ pub async fn create_post(
     Ok((post, post_media_list))
 }
 
-pub async fn find_posts(pool: &PgPool, offset: i64, limit: i64) -> Result<Vec<PostDetails>> {
+// Posts of suspended or banned users are hidden, see `user_is_visible`
+const VISIBLE_POSTS: &str = r#"
+    FROM posts p
+    JOIN users u ON u.id = p.user_id
+    WHERE p.deleted_at IS NULL
+        AND user_is_visible(u)"#;
+
+const TAG_POST_IDS: &str = r#"
+    SELECT pt.post_id FROM post_tags pt
+    JOIN tags t ON t.id = pt.tag_id
+    WHERE t.name = "#;
+
+pub async fn find_posts(
+    pool: &PgPool,
+    viewer_id: Option<&str>,
+    page: &Page,
+    limit: i64,
+) -> Result<Vec<PostDetails>> {
     let before = Instant::now();
 
-    // TODO: Remove likes_count and comments_count from the query
-    let posts: Vec<Post> = sqlx::query_as(
-        r#"
-        SELECT p.* FROM posts p
-        WHERE p.deleted_at IS NULL
-        ORDER BY p.created_at DESC
-        OFFSET $1
-        LIMIT $2
-    "#,
-    )
-    .bind(offset)
-    .bind(limit)
-    .fetch_all(pool)
-    .await?;
+    let mut query_builder = QueryBuilder::new(format!("SELECT p.* {VISIBLE_POSTS}"));
+
+    pagination::push_page(&mut query_builder, "p", page, limit);
+
+    let posts: Vec<Post> = query_builder.build_query_as().fetch_all(pool).await?;
 
     tracing::info!("[find_posts] Posts query time: {:?}", before.elapsed());
 
@@ -92,38 +133,85 @@ pub async fn find_posts(pool: &PgPool, offset: i64, limit: i64) -> Result<Vec<Po
 
     // Get all media for these posts in a single query
 
-    let post_details = get_post_details(pool, posts).await?;
+    let post_details = get_post_details(pool, posts, viewer_id).await?;
 
     Ok(post_details)
 }
 
 pub async fn find_user_posts(
     pool: &PgPool,
+    viewer_id: Option<&str>,
     user_id: &str,
-    offset: i64,
+    page: &Page,
     limit: i64,
 ) -> Result<Vec<PostDetails>> {
-    let posts: Vec<Post> = sqlx::query_as(
-        r#"
-        SELECT * FROM posts
-        WHERE user_id = $1 AND deleted_at IS NULL
-        ORDER BY created_at DESC
-        OFFSET $2
-        LIMIT $3
-    "#,
-    )
+    let mut query_builder =
+        QueryBuilder::new(format!("SELECT p.* {VISIBLE_POSTS} AND p.user_id = "));
+
+    query_builder.push_bind(user_id);
+
+    pagination::push_page(&mut query_builder, "p", page, limit);
+
+    let posts: Vec<Post> = query_builder.build_query_as().fetch_all(pool).await?;
+
+    let post_details = get_post_details(pool, posts, viewer_id).await?;
+
+    Ok(post_details)
+}
+
+pub async fn count_posts(pool: &PgPool) -> Result<i64> {
+    sqlx::query_scalar(&format!("SELECT COUNT(*) {VISIBLE_POSTS}"))
+        .fetch_one(pool)
+        .await
+}
+
+pub async fn count_user_posts(pool: &PgPool, user_id: &str) -> Result<i64> {
+    sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) {VISIBLE_POSTS} AND p.user_id = $1"
+    ))
     .bind(user_id)
-    .bind(offset)
-    .bind(limit)
-    .fetch_all(pool)
-    .await?;
+    .fetch_one(pool)
+    .await
+}
+
+/// Posts tagged with the normalized hashtag `tag`.
+pub async fn find_tag_posts(
+    pool: &PgPool,
+    viewer_id: Option<&str>,
+    tag: &str,
+    page: &Page,
+    limit: i64,
+) -> Result<Vec<PostDetails>> {
+    let mut query_builder = QueryBuilder::new(format!(
+        "SELECT p.* {VISIBLE_POSTS} AND p.id IN ({TAG_POST_IDS}"
+    ));
+
+    query_builder.push_bind(tag);
+    query_builder.push(")");
+
+    pagination::push_page(&mut query_builder, "p", page, limit);
+
+    let posts: Vec<Post> = query_builder.build_query_as().fetch_all(pool).await?;
 
-    let post_details = get_post_details(pool, posts).await?;
+    let post_details = get_post_details(pool, posts, viewer_id).await?;
 
     Ok(post_details)
 }
 
-pub async fn find_post_by_id(pool: &PgPool, id: &str) -> Result<Option<PostDetails>> {
+pub async fn count_tag_posts(pool: &PgPool, tag: &str) -> Result<i64> {
+    sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) {VISIBLE_POSTS} AND p.id IN ({TAG_POST_IDS}$1)"
+    ))
+    .bind(tag)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn find_post_by_id(
+    pool: &PgPool,
+    viewer_id: Option<&str>,
+    id: &str,
+) -> Result<Option<PostDetails>> {
     let post: Option<Post> = sqlx::query_as(
         r#"
         SELECT * FROM posts
@@ -136,10 +224,10 @@ pub async fn find_post_by_id(pool: &PgPool, id: &str) -> Result<Option<PostDetai
 
     match post {
         Some(post) => {
-            let post_details_v = get_post_details(pool, vec![post]).await?;
+            let post_details_v = get_post_details(pool, vec![post], viewer_id).await?;
 
             let post_details = post_details_v
-                .get(0)
+                .first()
                 .cloned()
                 .expect("[find_post_by_id] post_details not found");
 
@@ -155,24 +243,52 @@ pub async fn update_post(
     post_id: &str,
     body: UpdatePostDto,
 ) -> Result<Option<Post>> {
+    let mut tx = pool.begin().await?;
+
     let updated_post: Option<Post> = sqlx::query_as(
         r#"UPDATE posts SET content = $1 WHERE user_id = $2 AND id = $3 AND deleted_at IS NULL RETURNING *"#,
     )
     .bind(body.content)
     .bind(user_id)
     .bind(post_id)
-    .fetch_optional(pool)
+    .fetch_optional(&mut *tx)
     .await?;
 
+    if let Some(post) = &updated_post {
+        service::tag::sync_post_tags(&mut tx, &post.id, &hashtag::extract_hashtags(&post.content))
+            .await?;
+
+        let mentioned_user_ids =
+            service::mention::sync_mentions(&mut tx, MentionTarget::Post(&post.id), &post.content)
+                .await?;
+
+        service::notification::notify(
+            &mut *tx,
+            NotificationKind::Mention,
+            user_id,
+            &mentioned_user_ids,
+            Some(&post.id),
+            None,
+        )
+        .await?;
+    }
+
+    tx.commit().await?;
+
     Ok(updated_post)
 }
 
-pub async fn delete_post(pool: &PgPool, user_id: &str, post_id: &str) -> Result<Option<String>> {
+pub async fn delete_post(
+    pool: &PgPool,
+    actor: &CurrentUser,
+    post_id: &str,
+) -> Result<Option<String>> {
     let deleted_post: Option<String> = sqlx::query_scalar(
-        r#"UPDATE posts SET deleted_at = NOW() where user_id = $1 AND id = $2 RETURNING id"#,
+        r#"UPDATE posts SET deleted_at = NOW() WHERE (user_id = $1 OR $3) AND id = $2 AND deleted_at IS NULL RETURNING id"#,
     )
-    .bind(user_id)
+    .bind(&actor.id)
     .bind(post_id)
+    .bind(actor.can_moderate())
     .fetch_optional(pool)
     .await?;
 
@@ -180,56 +296,97 @@ pub async fn delete_post(pool: &PgPool, user_id: &str, post_id: &str) -> Result<
 }
 
 pub async fn like_post(pool: &PgPool, user_id: &str, post_id: &str) -> Result<bool> {
-    sqlx::query(r#"SELECT id FROM posts WHERE id = $1 AND deleted_at IS NULL"#)
-        .bind(&post_id)
-        .fetch_one(pool)
-        .await?;
-
-    let has_liked: bool = sqlx::query_scalar(
+    let post_author_id: String =
+        sqlx::query_scalar(r#"SELECT user_id FROM posts WHERE id = $1 AND deleted_at IS NULL"#)
+            .bind(post_id)
+            .fetch_one(pool)
+            .await?;
+
+    // Toggles the like. Concurrent requests never fail on the unique index, and only
+    // the request that actually added or removed the like notifies
+    let unliked: Option<String> = sqlx::query_scalar(
         r#"
-        SELECT EXISTS (
-            SELECT 1 FROM post_likes
-            WHERE user_id = $1 AND post_id = $2
-        )
+        DELETE FROM post_likes
+        WHERE user_id = $1 AND post_id = $2
+        RETURNING id
     "#,
     )
     .bind(user_id)
     .bind(post_id)
-    .fetch_one(pool)
+    .fetch_optional(pool)
     .await?;
 
-    let query = if has_liked {
-        sqlx::query(
-            r#"
-            DELETE FROM post_likes
-            WHERE user_id = $1 AND post_id = $2
-        "#,
-        )
-    } else {
-        sqlx::query(
+    if unliked.is_none() {
+        let liked: Option<String> = sqlx::query_scalar(
             r#"
             INSERT INTO post_likes (user_id, post_id)
             VALUES ($1, $2)
+            ON CONFLICT (post_id, user_id) DO NOTHING
+            RETURNING id
         "#,
         )
-    };
+        .bind(user_id)
+        .bind(post_id)
+        .fetch_optional(pool)
+        .await?;
 
-    query.bind(user_id).bind(post_id).execute(pool).await?;
+        if liked.is_none() {
+            return Ok(true);
+        }
+    }
 
-    let has_liked = !has_liked;
+    let has_liked = unliked.is_none();
+
+    match has_liked {
+        true => {
+            service::notification::notify(
+                pool,
+                NotificationKind::Like,
+                user_id,
+                &[post_author_id],
+                Some(post_id),
+                None,
+            )
+            .await?
+        }
+        false => {
+            service::notification::retract(
+                pool,
+                NotificationKind::Like,
+                user_id,
+                &post_author_id,
+                Some(post_id),
+                None,
+            )
+            .await?
+        }
+    }
 
     Ok(has_liked)
 }
 
-pub async fn get_post_details(pool: &PgPool, posts: Vec<Post>) -> Result<Vec<PostDetails>> {
+pub async fn get_post_details(
+    pool: &PgPool,
+    posts: Vec<Post>,
+    viewer_id: Option<&str>,
+) -> Result<Vec<PostDetails>> {
     let post_ids: Vec<String> = posts.iter().map(|p| p.id.clone()).collect();
     let user_ids: Vec<String> = posts.iter().map(|p| p.user_id.clone()).collect();
 
-    let (mut media_by_post, author_by_post, comments_count_by_id, likes_count_by_id) = tokio::try_join!(
+    let (
+        mut media_by_post,
+        author_by_post,
+        comments_count_by_id,
+        likes_count_by_id,
+        liked_post_ids,
+        mut mentions_by_post,
+    ) = tokio::try_join!(
         get_media_by_post_map(pool, &post_ids),
         get_author_by_id_map(pool, &user_ids),
         get_comments_count_by_id_map(pool, &post_ids),
         get_likes_count_by_id_map(pool, &post_ids),
+        get_liked_post_ids(pool, &post_ids, viewer_id),
+        service::mention::get_mentions_by_target_map(pool, &post_ids),
     )?;
 
     // Combine posts with their media
@@ -245,16 +402,23 @@ pub async fn get_post_details(pool: &PgPool, posts: Vec<Post>) -> Result<Vec<Pos
                 .expect("[get_post_details] author not found");
 
             let media = media_by_post.remove(&post_id).unwrap_or_default();
+            let mentions = mentions_by_post.remove(&post_id).unwrap_or_default();
 
             let likes_count = likes_count_by_id.get(&post_id).cloned().unwrap_or(0);
             let comments_count = comments_count_by_id.get(&post_id).cloned().unwrap_or(0);
 
+            let viewer = viewer_id.map(|_| PostViewerContext {
+                liked_by_me: liked_post_ids.contains(&post_id),
+            });
+
             PostDetails {
                 post,
                 author,
                 media,
+                mentions,
                 likes_count,
                 comments_count,
+                viewer,
             }
         })
         .collect();
@@ -278,10 +442,16 @@ async fn get_media_by_post_map(
     Ok(media_by_post)
 }
 
-async fn get_author_by_id_map(pool: &PgPool, user_ids: &[String]) -> Result<HashMap<String, User>> {
-    let users: Vec<User> = service::user::get_users_by_ids(pool, &user_ids).await?;
+async fn get_author_by_id_map(
+    pool: &PgPool,
+    user_ids: &[String],
+) -> Result<HashMap<String, Author>> {
+    let users: Vec<User> = service::user::get_users_by_ids(pool, user_ids).await?;
 
-    let user_by_id: HashMap<String, User> = users.into_iter().map(|u| (u.id.clone(), u)).collect();
+    let user_by_id: HashMap<String, Author> = users
+        .into_iter()
+        .map(|u| (u.id.clone(), Author::from(u)))
+        .collect();
 
     Ok(user_by_id)
 }
@@ -347,3 +517,27 @@ async fn get_likes_count_by_id_map(
 
     Ok(likes_count_map)
 }
+
+async fn get_liked_post_ids(
+    pool: &PgPool,
+    post_ids: &[String],
+    viewer_id: Option<&str>,
+) -> Result<HashSet<String>> {
+    let viewer_id = match viewer_id {
+        Some(viewer_id) => viewer_id,
+        None => return Ok(HashSet::new()),
+    };
+
+    let liked_post_ids: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT post_id FROM post_likes
+        WHERE user_id = $1 AND post_id = ANY($2)
+        "#,
+    )
+    .bind(viewer_id)
+    .bind(post_ids)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(liked_post_ids.into_iter().collect())
+}
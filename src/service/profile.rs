@@ -0,0 +1,60 @@
+use sqlx::{PgPool, Result};
+
+use crate::models::PublicProfile;
+
+const PUBLIC_PROFILE_QUERY: &str = r#"
+    SELECT
+        u.id,
+        u.username,
+        u.profile_image_url,
+        u.role,
+        u.created_at AS joined_at,
+        (
+            SELECT COUNT(*) FROM posts p
+            WHERE p.user_id = u.id AND p.deleted_at IS NULL
+        ) AS posts_count,
+        (
+            SELECT COUNT(*) FROM post_comments c
+            WHERE c.user_id = u.id AND c.deleted_at IS NULL
+        ) AS comments_count,
+        (
+            SELECT COUNT(*) FROM post_likes l
+            JOIN posts p ON p.id = l.post_id
+            WHERE p.user_id = u.id AND p.deleted_at IS NULL
+        ) AS likes_received,
+        (
+            SELECT COUNT(*) FROM follows f
+            JOIN users fu ON fu.id = f.follower_id
+            WHERE f.followee_id = u.id AND fu.deleted_at IS NULL
+        ) AS followers_count,
+        (
+            SELECT COUNT(*) FROM follows f
+            JOIN users fu ON fu.id = f.followee_id
+            WHERE f.follower_id = u.id AND fu.deleted_at IS NULL
+        ) AS following_count
+    FROM users u
+"#;
+
+pub async fn get_public_profile_by_id(
+    pool: &PgPool,
+    user_id: &str,
+) -> Result<Option<PublicProfile>> {
+    sqlx::query_as(&format!(
+        "{PUBLIC_PROFILE_QUERY} WHERE u.id = $1 AND u.deleted_at IS NULL"
+    ))
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn get_public_profile_by_username(
+    pool: &PgPool,
+    username: &str,
+) -> Result<Option<PublicProfile>> {
+    sqlx::query_as(&format!(
+        "{PUBLIC_PROFILE_QUERY} WHERE u.username = $1 AND u.deleted_at IS NULL"
+    ))
+    .bind(username)
+    .fetch_optional(pool)
+    .await
+}
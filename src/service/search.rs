@@ -0,0 +1,143 @@
+use sqlx::{PgPool, Postgres, QueryBuilder, Result};
+
+use crate::{
+    constants::SEARCH_RECENCY_HALF_LIFE,
+    dtos::search::{SearchQueryDto, SearchScope},
+    models::SearchHit,
+};
+
+const HEADLINE_OPTIONS: &str = "StartSel=<mark>, StopSel=</mark>, MaxWords=35, MinWords=15, MaxFragments=2, FragmentDelimiter=\" ... \"";
+
+/// Posts and comments matching `query`, most relevant first. Relevance is the
+/// `ts_rank` of the match decayed by age, see [`SEARCH_RECENCY_HALF_LIFE`].
+pub async fn search(
+    pool: &PgPool,
+    query: &SearchQueryDto,
+    offset: i64,
+    limit: i64,
+) -> Result<Vec<SearchHit>> {
+    let mut query_builder = QueryBuilder::new("SELECT * FROM (");
+
+    push_hits(&mut query_builder, query, false);
+
+    query_builder.push(") hits ORDER BY rank DESC, created_at DESC, id DESC OFFSET ");
+    query_builder.push_bind(offset);
+    // One extra row tells whether there is a next page
+    query_builder.push(" LIMIT ");
+    query_builder.push_bind(limit + 1);
+
+    query_builder.build_query_as().fetch_all(pool).await
+}
+
+pub async fn count_search_hits(pool: &PgPool, query: &SearchQueryDto) -> Result<i64> {
+    let mut query_builder = QueryBuilder::new("SELECT COUNT(*) FROM (");
+
+    push_hits(&mut query_builder, query, true);
+
+    query_builder.push(") hits");
+
+    query_builder.build_query_scalar().fetch_one(pool).await
+}
+
+/// Pushes the union of matching posts and comments. When the rows are only counted
+/// a constant is selected instead, skipping highlighting and ranking.
+fn push_hits(query_builder: &mut QueryBuilder<'_, Postgres>, query: &SearchQueryDto, count: bool) {
+    if query.scope != SearchScope::Comments {
+        query_builder.push("SELECT ");
+
+        match count {
+            true => {
+                query_builder.push("1");
+            }
+            false => push_columns(query_builder, "post", "p.id", "p"),
+        }
+
+        query_builder.push(
+            r#"
+            FROM posts p
+            JOIN users u ON u.id = p.user_id,
+            websearch_to_tsquery('english', "#,
+        );
+        query_builder.push_bind(query.q.clone());
+        query_builder.push(
+            r#") query
+            WHERE p.search_vector @@ query AND p.deleted_at IS NULL"#,
+        );
+
+        push_filters(query_builder, query, "p");
+    }
+
+    if query.scope == SearchScope::All {
+        query_builder.push(" UNION ALL ");
+    }
+
+    if query.scope != SearchScope::Posts {
+        query_builder.push("SELECT ");
+
+        match count {
+            true => {
+                query_builder.push("1");
+            }
+            false => push_columns(query_builder, "comment", "c.post_id", "c"),
+        }
+
+        query_builder.push(
+            r#"
+            FROM post_comments c
+            JOIN posts p ON p.id = c.post_id
+            JOIN users u ON u.id = c.user_id,
+            websearch_to_tsquery('english', "#,
+        );
+        query_builder.push_bind(query.q.clone());
+        query_builder.push(
+            r#") query
+            WHERE c.search_vector @@ query AND c.deleted_at IS NULL AND p.deleted_at IS NULL"#,
+        );
+
+        push_filters(query_builder, query, "c");
+    }
+}
+
+/// Columns of a [`SearchHit`] out of the matched row `alias`, joined with its post
+/// as `p` and its author as `u`. The content is escaped before highlighting so the
+/// `<mark>` tags are the only markup of the snippet.
+fn push_columns(
+    query_builder: &mut QueryBuilder<'_, Postgres>,
+    kind: &str,
+    post_id: &str,
+    alias: &str,
+) {
+    query_builder.push(format!(
+        "'{kind}' AS kind, {alias}.id, {post_id} AS post_id, {alias}.user_id, u.username, p.title, ts_headline('english', html_escape({alias}.content), query, "
+    ));
+    query_builder.push_bind(HEADLINE_OPTIONS);
+    query_builder.push(format!(
+        ") AS snippet, (ts_rank({alias}.search_vector, query) * power(0.5, EXTRACT(EPOCH FROM NOW() - {alias}.created_at) / "
+    ));
+    query_builder.push_bind(SEARCH_RECENCY_HALF_LIFE.as_secs_f64());
+    query_builder.push(format!("))::REAL AS rank, {alias}.created_at"));
+}
+
+fn push_filters(
+    query_builder: &mut QueryBuilder<'_, Postgres>,
+    query: &SearchQueryDto,
+    alias: &str,
+) {
+    // Content of suspended or banned users is hidden, see `user_is_visible`
+    query_builder.push(" AND user_is_visible(u)");
+
+    if let Some(author_id) = &query.author_id {
+        query_builder.push(format!(" AND {alias}.user_id = "));
+        query_builder.push_bind(author_id.clone());
+    }
+
+    if let Some(from) = query.from {
+        query_builder.push(format!(" AND {alias}.created_at >= "));
+        query_builder.push_bind(from);
+    }
+
+    if let Some(to) = query.to {
+        query_builder.push(format!(" AND {alias}.created_at <= "));
+        query_builder.push_bind(to);
+    }
+}
@@ -0,0 +1,220 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Result};
+
+use crate::{
+    core::extractors::client_info::ClientInfo,
+    models::{RefreshToken, Session, SessionOwner},
+};
+
+pub enum RefreshTokenRotation {
+    Rotated(Session),
+    Reused,
+    Invalid,
+}
+
+pub async fn create_session(
+    pool: &PgPool,
+    user_id: &str,
+    refresh_token_hash: &str,
+    expires_at: DateTime<Utc>,
+    client: &ClientInfo,
+) -> Result<Session> {
+    let mut tx = pool.begin().await?;
+
+    let session: Session = sqlx::query_as(
+        r#"
+        INSERT INTO sessions (user_id, expires_at, user_agent, ip_address)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+    "#,
+    )
+    .bind(user_id)
+    .bind(expires_at)
+    .bind(&client.user_agent)
+    .bind(&client.ip_address)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO refresh_tokens (session_id, token_hash, expires_at)
+        VALUES ($1, $2, $3)
+    "#,
+    )
+    .bind(&session.id)
+    .bind(refresh_token_hash)
+    .bind(expires_at)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(session)
+}
+
+/// Exchanges a refresh token for a new one.
+///
+/// Every refresh token can be used exactly once. Presenting a token that was already
+/// rotated means it leaked, so the whole session is revoked.
+pub async fn rotate_refresh_token(
+    pool: &PgPool,
+    refresh_token_hash: &str,
+    new_refresh_token_hash: &str,
+    expires_at: DateTime<Utc>,
+    client: &ClientInfo,
+) -> Result<RefreshTokenRotation> {
+    let mut tx = pool.begin().await?;
+
+    let refresh_token: Option<RefreshToken> =
+        sqlx::query_as(r#"SELECT * FROM refresh_tokens WHERE token_hash = $1 FOR UPDATE"#)
+            .bind(refresh_token_hash)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+    let refresh_token = match refresh_token {
+        Some(refresh_token) => refresh_token,
+        None => return Ok(RefreshTokenRotation::Invalid),
+    };
+
+    if refresh_token.used_at.is_some() {
+        sqlx::query(
+            r#"UPDATE sessions SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL"#,
+        )
+        .bind(&refresh_token.session_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        return Ok(RefreshTokenRotation::Reused);
+    }
+
+    if refresh_token.expires_at < Utc::now() {
+        return Ok(RefreshTokenRotation::Invalid);
+    }
+
+    let session: Option<Session> = sqlx::query_as(
+        r#"
+        UPDATE sessions
+        SET
+            expires_at = $1,
+            last_seen_at = NOW(),
+            user_agent = COALESCE($3, user_agent),
+            ip_address = COALESCE($4, ip_address)
+        WHERE id = $2 AND revoked_at IS NULL AND expires_at > NOW()
+        RETURNING *
+    "#,
+    )
+    .bind(expires_at)
+    .bind(&refresh_token.session_id)
+    .bind(&client.user_agent)
+    .bind(&client.ip_address)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let session = match session {
+        Some(session) => session,
+        None => return Ok(RefreshTokenRotation::Invalid),
+    };
+
+    sqlx::query(r#"UPDATE refresh_tokens SET used_at = NOW() WHERE id = $1"#)
+        .bind(&refresh_token.id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO refresh_tokens (session_id, token_hash, expires_at)
+        VALUES ($1, $2, $3)
+    "#,
+    )
+    .bind(&session.id)
+    .bind(new_refresh_token_hash)
+    .bind(expires_at)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(RefreshTokenRotation::Rotated(session))
+}
+
+/// Returns the owner of the session if the session is still active, bumping its
+/// `last_seen_at`.
+///
+/// `last_seen_at` is only written once a minute so authenticated requests don't turn
+/// into a write each.
+pub async fn touch_active_session(pool: &PgPool, session_id: &str) -> Result<Option<SessionOwner>> {
+    sqlx::query_as(
+        r#"
+        WITH active AS (
+            SELECT id, user_id, last_seen_at FROM sessions
+            WHERE id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        ), touched AS (
+            UPDATE sessions s
+            SET last_seen_at = NOW()
+            FROM active
+            WHERE s.id = active.id
+                AND (active.last_seen_at IS NULL OR active.last_seen_at < NOW() - INTERVAL '1 minute')
+        )
+        SELECT u.id, u.role, u.status, u.suspended_until, u.status_reason FROM active
+        JOIN users u ON u.id = active.user_id
+    "#,
+    )
+    .bind(session_id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn get_active_user_sessions(pool: &PgPool, user_id: &str) -> Result<Vec<Session>> {
+    sqlx::query_as(
+        r#"
+        SELECT * FROM sessions
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        ORDER BY last_seen_at DESC
+    "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn revoke_session(
+    pool: &PgPool,
+    user_id: &str,
+    session_id: &str,
+) -> Result<Option<String>> {
+    sqlx::query_scalar(
+        r#"
+        UPDATE sessions
+        SET revoked_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL
+        RETURNING id
+    "#,
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Revokes every active session of the user except `current_session_id`.
+pub async fn revoke_other_sessions(
+    pool: &PgPool,
+    user_id: &str,
+    current_session_id: &str,
+) -> Result<u64> {
+    let result = sqlx::query(
+        r#"
+        UPDATE sessions
+        SET revoked_at = NOW()
+        WHERE user_id = $1 AND id <> $2 AND revoked_at IS NULL
+    "#,
+    )
+    .bind(user_id)
+    .bind(current_session_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
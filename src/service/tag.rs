@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Result, Transaction};
+
+use crate::models::TrendingTag;
+
+/// Makes `tags` the hashtags of the post, adding missing tags and unlinking the
+/// ones no longer used by it.
+pub async fn sync_post_tags(
+    tx: &mut Transaction<'_, Postgres>,
+    post_id: &str,
+    tags: &[String],
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO tags (name)
+        SELECT UNNEST($1::VARCHAR[])
+        ON CONFLICT (name) DO NOTHING
+    "#,
+    )
+    .bind(tags)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM post_tags
+        WHERE post_id = $1
+            AND tag_id NOT IN (SELECT id FROM tags WHERE name = ANY($2))
+    "#,
+    )
+    .bind(post_id)
+    .bind(tags)
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO post_tags (post_id, tag_id)
+        SELECT $1, id FROM tags WHERE name = ANY($2)
+        ON CONFLICT (post_id, tag_id) DO NOTHING
+    "#,
+    )
+    .bind(post_id)
+    .bind(tags)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Tags used by the most authors in posts created since `since`, deleted posts and
+/// posts of suspended or banned users are not counted.
+pub async fn get_trending_tags(
+    pool: &PgPool,
+    since: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<TrendingTag>> {
+    sqlx::query_as(
+        r#"
+        SELECT t.name, COUNT(*) AS posts_count, COUNT(DISTINCT p.user_id) AS authors_count
+        FROM post_tags pt
+        JOIN tags t ON t.id = pt.tag_id
+        JOIN posts p ON p.id = pt.post_id
+        JOIN users u ON u.id = p.user_id
+        WHERE p.deleted_at IS NULL
+            AND p.created_at >= $1
+            AND user_is_visible(u)
+        GROUP BY t.name
+        ORDER BY authors_count DESC, posts_count DESC, t.name
+        LIMIT $2
+    "#,
+    )
+    .bind(since)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,261 @@
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, Result, Transaction};
+
+use crate::{
+    core::utils::{token, totp},
+    models::{TotpCredential, TwoFactorChallenge},
+};
+
+pub async fn get_totp_credential(pool: &PgPool, user_id: &str) -> Result<Option<TotpCredential>> {
+    sqlx::query_as(r#"SELECT * FROM totp_credentials WHERE user_id = $1"#)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+}
+
+pub async fn is_two_factor_enabled(pool: &PgPool, user_id: &str) -> Result<bool> {
+    sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM totp_credentials
+            WHERE user_id = $1 AND enabled_at IS NOT NULL
+        )
+    "#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Stores a new secret awaiting confirmation, replacing any previous unconfirmed one.
+///
+/// Returns `None` when the user already has two-factor authentication enabled.
+pub async fn create_pending_totp_credential(
+    pool: &PgPool,
+    user_id: &str,
+    secret: &str,
+) -> Result<Option<TotpCredential>> {
+    sqlx::query_as(
+        r#"
+        INSERT INTO totp_credentials (user_id, secret)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE
+        SET secret = EXCLUDED.secret, last_used_step = NULL, created_at = NOW()
+        WHERE totp_credentials.enabled_at IS NULL
+        RETURNING *
+    "#,
+    )
+    .bind(user_id)
+    .bind(secret)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Records `step` as used, returning `false` if it (or a later step) was already
+/// used so the same code can't be accepted twice.
+pub async fn record_totp_step(pool: &PgPool, user_id: &str, step: i64) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE totp_credentials
+        SET last_used_step = $2
+        WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+    "#,
+    )
+    .bind(user_id)
+    .bind(step)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Enables the pending credential and issues the user's recovery codes.
+pub async fn enable_totp_credential(
+    pool: &PgPool,
+    user_id: &str,
+    recovery_code_hashes: &[String],
+) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query(
+        r#"
+        UPDATE totp_credentials
+        SET enabled_at = NOW()
+        WHERE user_id = $1 AND enabled_at IS NULL
+    "#,
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    insert_recovery_codes(&mut tx, user_id, recovery_code_hashes).await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+pub async fn disable_totp_credential(pool: &PgPool, user_id: &str) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(r#"DELETE FROM totp_credentials WHERE user_id = $1"#)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(r#"DELETE FROM recovery_codes WHERE user_id = $1"#)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await
+}
+
+/// Invalidates every recovery code of the user and issues new ones.
+pub async fn replace_recovery_codes(
+    pool: &PgPool,
+    user_id: &str,
+    recovery_code_hashes: &[String],
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    insert_recovery_codes(&mut tx, user_id, recovery_code_hashes).await?;
+
+    tx.commit().await
+}
+
+async fn insert_recovery_codes(
+    tx: &mut Transaction<'_, Postgres>,
+    user_id: &str,
+    recovery_code_hashes: &[String],
+) -> Result<()> {
+    sqlx::query(r#"DELETE FROM recovery_codes WHERE user_id = $1"#)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO recovery_codes (user_id, code_hash)
+        SELECT $1, code_hash FROM UNNEST($2::VARCHAR[]) AS code_hash
+    "#,
+    )
+    .bind(user_id)
+    .bind(recovery_code_hashes)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Marks the recovery code as used, returning whether it was valid.
+pub async fn consume_recovery_code(pool: &PgPool, user_id: &str, code_hash: &str) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE recovery_codes
+        SET used_at = NOW()
+        WHERE id = (
+            SELECT id FROM recovery_codes
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            LIMIT 1
+        )
+    "#,
+    )
+    .bind(user_id)
+    .bind(code_hash)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn count_unused_recovery_codes(pool: &PgPool, user_id: &str) -> Result<i64> {
+    sqlx::query_scalar(
+        r#"SELECT COUNT(*) FROM recovery_codes WHERE user_id = $1 AND used_at IS NULL"#,
+    )
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn create_two_factor_challenge(
+    pool: &PgPool,
+    user_id: &str,
+    token_hash: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<TwoFactorChallenge> {
+    sqlx::query_as(
+        r#"
+        INSERT INTO two_factor_challenges (user_id, token_hash, expires_at)
+        VALUES ($1, $2, $3)
+        RETURNING *
+    "#,
+    )
+    .bind(user_id)
+    .bind(token_hash)
+    .bind(expires_at)
+    .fetch_one(pool)
+    .await
+}
+
+/// Atomically records an attempt against a pending challenge.
+///
+/// Returns `None` when the challenge doesn't exist, expired, was already used or
+/// ran out of attempts.
+pub async fn register_two_factor_attempt(
+    pool: &PgPool,
+    token_hash: &str,
+    max_attempts: i32,
+) -> Result<Option<TwoFactorChallenge>> {
+    sqlx::query_as(
+        r#"
+        UPDATE two_factor_challenges
+        SET attempts = attempts + 1
+        WHERE token_hash = $1
+            AND consumed_at IS NULL
+            AND expires_at > NOW()
+            AND attempts < $2
+        RETURNING *
+    "#,
+    )
+    .bind(token_hash)
+    .bind(max_attempts)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Marks the challenge as used, returning `false` if it was used concurrently.
+pub async fn consume_two_factor_challenge(pool: &PgPool, challenge_id: &str) -> Result<bool> {
+    let result = sqlx::query(
+        r#"
+        UPDATE two_factor_challenges
+        SET consumed_at = NOW()
+        WHERE id = $1 AND consumed_at IS NULL
+    "#,
+    )
+    .bind(challenge_id)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Checks a code from the user's authenticator app, falling back to their unused
+/// recovery codes.
+pub async fn verify_second_factor(
+    pool: &PgPool,
+    credential: &TotpCredential,
+    code: &str,
+) -> Result<bool> {
+    let code = code.trim();
+
+    if let Some(step) = totp::verify_code(&credential.secret, code, totp::current_step()) {
+        return record_totp_step(pool, &credential.user_id, step).await;
+    }
+
+    consume_recovery_code(pool, &credential.user_id, &token::hash_recovery_code(code)).await
+}
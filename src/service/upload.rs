@@ -0,0 +1,66 @@
+use sqlx::{PgPool, Result};
+
+/// Records a file handed out by `/upload`. Anonymous uploads have no owner until an
+/// account claims them.
+pub async fn create_upload(pool: &PgPool, url: &str, user_id: Option<&str>) -> Result<()> {
+    sqlx::query(r#"INSERT INTO uploads (url, user_id) VALUES ($1, $2)"#)
+        .bind(url)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Whether every url was uploaded by the user.
+pub async fn owns_uploads(pool: &PgPool, user_id: &str, urls: &[String]) -> Result<bool> {
+    let owned: i64 =
+        sqlx::query_scalar(r#"SELECT COUNT(*) FROM uploads WHERE user_id = $1 AND url = ANY($2)"#)
+            .bind(user_id)
+            .bind(urls)
+            .fetch_one(pool)
+            .await?;
+
+    let mut urls = urls.to_vec();
+    urls.sort();
+    urls.dedup();
+
+    Ok(owned == urls.len() as i64)
+}
+
+/// Gives an anonymous upload to the user, uploads that already have an owner are
+/// left alone.
+pub async fn claim_upload(pool: &PgPool, user_id: &str, url: &str) -> Result<()> {
+    sqlx::query(r#"UPDATE uploads SET user_id = $1 WHERE url = $2 AND user_id IS NULL"#)
+        .bind(user_id)
+        .bind(url)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Urls of the files the user uploaded that no post, message or profile uses.
+pub async fn get_unused_uploads(pool: &PgPool, user_id: &str) -> Result<Vec<String>> {
+    sqlx::query_scalar(
+        r#"
+        SELECT up.url FROM uploads up
+        WHERE up.user_id = $1
+            AND NOT EXISTS (SELECT 1 FROM posts_media pm WHERE pm.media_url = up.url)
+            AND NOT EXISTS (SELECT 1 FROM message_attachments ma WHERE ma.media_url = up.url)
+            AND NOT EXISTS (SELECT 1 FROM users u WHERE u.profile_image_url = up.url)
+    "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn delete_uploads(pool: &PgPool, urls: &[String]) -> Result<()> {
+    sqlx::query(r#"DELETE FROM uploads WHERE url = ANY($1)"#)
+        .bind(urls)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -1,6 +1,10 @@
 use sqlx::{PgPool, Result};
 
-use crate::{dtos::user::UpdateProfileDto, models::User};
+use crate::{
+    dtos::user::UpdateProfileDto,
+    models::{AccountState, User, UserRole, UserSearchResult},
+    service,
+};
 
 pub async fn create_user_if_not_exists(
     pool: &PgPool,
@@ -23,10 +27,14 @@ pub async fn create_user_if_not_exists(
     )
     .bind(email)
     .bind(username)
-    .bind(profile_image_url)
+    .bind(&profile_image_url)
     .fetch_one(pool)
     .await?;
 
+    if let Some(url) = &profile_image_url {
+        service::upload::claim_upload(pool, &user.id, url).await?;
+    }
+
     Ok(user)
 }
 
@@ -56,6 +64,48 @@ pub async fn get_user_by_username(pool: &PgPool, username: &str) -> Result<Optio
     Ok(user)
 }
 
+/// Users whose username starts with or is similar to `query`. Exact matches come
+/// first, then prefix matches, each preferring accounts followed by the viewer.
+pub async fn search_users(
+    pool: &PgPool,
+    viewer_id: Option<&str>,
+    query: &str,
+    limit: i64,
+) -> Result<Vec<UserSearchResult>> {
+    let prefix = format!("{}%", escape_like(query));
+
+    sqlx::query_as(
+        r#"
+        SELECT u.id, u.username, u.profile_image_url,
+            CASE WHEN $1::VARCHAR IS NULL THEN NULL ELSE f.id IS NOT NULL END AS followed_by_me
+        FROM users u
+        LEFT JOIN follows f ON f.followee_id = u.id AND f.follower_id = $1
+        WHERE u.deleted_at IS NULL
+            AND user_is_visible(u)
+            AND (u.username ILIKE $3 OR u.username % $2)
+        ORDER BY lower(u.username) = lower($2) DESC,
+            u.username ILIKE $3 DESC,
+            f.id IS NOT NULL DESC,
+            similarity(u.username, $2) DESC,
+            u.username
+        LIMIT $4
+    "#,
+    )
+    .bind(viewer_id)
+    .bind(query)
+    .bind(prefix)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
 pub async fn get_user_by_email(pool: &PgPool, email: &str) -> Result<Option<User>> {
     let user: Option<User> = sqlx::query_as(r#"SELECT * FROM users WHERE email = $1"#)
         .bind(email)
@@ -71,6 +121,10 @@ pub async fn update_user(pool: &PgPool, user_id: &str, payload: UpdateProfileDto
         None => return Err(sqlx::Error::RowNotFound),
     };
 
+    if let Some(url) = &payload.profile_image_url {
+        service::upload::claim_upload(pool, user_id, url).await?;
+    }
+
     let profile_image_url = match payload.profile_image_url {
         Some(url) => Some(url),
         None => user.profile_image_url,
@@ -87,3 +141,73 @@ pub async fn update_user(pool: &PgPool, user_id: &str, payload: UpdateProfileDto
 
     Ok(user)
 }
+
+pub async fn mark_user_verified(pool: &PgPool, user_id: &str) -> Result<User> {
+    let user: User =
+        sqlx::query_as(r#"UPDATE users SET is_verified = TRUE WHERE id = $1 RETURNING *"#)
+            .bind(user_id)
+            .fetch_one(pool)
+            .await?;
+
+    Ok(user)
+}
+
+pub async fn get_users(pool: &PgPool, offset: i64, limit: i64) -> Result<Vec<User>> {
+    let users: Vec<User> = sqlx::query_as(
+        r#"
+        SELECT * FROM users
+        ORDER BY created_at DESC
+        OFFSET $1
+        LIMIT $2
+    "#,
+    )
+    .bind(offset)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(users)
+}
+
+pub async fn update_user_role(
+    pool: &PgPool,
+    user_id: &str,
+    role: UserRole,
+) -> Result<Option<User>> {
+    let user: Option<User> =
+        sqlx::query_as(r#"UPDATE users SET role = $1 WHERE id = $2 RETURNING *"#)
+            .bind(role)
+            .bind(user_id)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(user)
+}
+
+pub async fn get_account_state(pool: &PgPool, user_id: &str) -> Result<Option<AccountState>> {
+    sqlx::query_as(r#"SELECT status, suspended_until, status_reason FROM users WHERE id = $1"#)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+}
+
+pub async fn update_account_state(
+    pool: &PgPool,
+    user_id: &str,
+    account_state: &AccountState,
+) -> Result<Option<User>> {
+    sqlx::query_as(
+        r#"
+        UPDATE users
+        SET status = $2, suspended_until = $3, status_reason = $4
+        WHERE id = $1
+        RETURNING *
+    "#,
+    )
+    .bind(user_id)
+    .bind(account_state.status)
+    .bind(account_state.suspended_until)
+    .bind(&account_state.status_reason)
+    .fetch_optional(pool)
+    .await
+}
@@ -1,28 +1,131 @@
+use chrono::{DateTime, Utc};
 use sqlx::{PgPool, Result};
 
 use crate::models::VerificationPin;
 
-pub async fn get_verification_pin(
+/// Returns the most recently issued pin for the email that has not been invalidated.
+pub async fn get_active_verification_pin(
     pool: &PgPool,
     email: &str,
-    pin: &str,
 ) -> Result<Option<VerificationPin>> {
-    sqlx::query_as(r#"SELECT * FROM verification_pins WHERE email = $1 AND pin = $2"#)
+    sqlx::query_as(
+        r#"
+        SELECT * FROM verification_pins
+        WHERE email = $1 AND invalidated_at IS NULL
+        ORDER BY created_at DESC
+        LIMIT 1
+    "#,
+    )
+    .bind(email)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn get_latest_verification_pin(
+    pool: &PgPool,
+    email: &str,
+) -> Result<Option<VerificationPin>> {
+    sqlx::query_as(
+        r#"
+        SELECT * FROM verification_pins
+        WHERE email = $1
+        ORDER BY created_at DESC
+        LIMIT 1
+    "#,
+    )
+    .bind(email)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Records a verification attempt for the email and returns how many were made since
+/// `since`, this one included. Attempts older than the window are cleared.
+///
+/// Attempts for the same email are serialized, so concurrent guesses can't all count
+/// against a snapshot without each other.
+pub async fn register_verification_attempt(
+    pool: &PgPool,
+    email: &str,
+    since: DateTime<Utc>,
+) -> Result<i64> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(r#"SELECT pg_advisory_xact_lock(hashtext($1))"#)
         .bind(email)
-        .bind(pin)
-        .fetch_optional(pool)
-        .await
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(r#"DELETE FROM verification_attempts WHERE email = $1 AND created_at <= $2"#)
+        .bind(email)
+        .bind(since)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(r#"INSERT INTO verification_attempts (email) VALUES ($1)"#)
+        .bind(email)
+        .execute(&mut *tx)
+        .await?;
+
+    let attempts: i64 = sqlx::query_scalar(
+        r#"SELECT COUNT(*) FROM verification_attempts WHERE email = $1 AND created_at > $2"#,
+    )
+    .bind(email)
+    .bind(since)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(attempts)
+}
+
+/// Deletes every pin issued for the email, returning whether the given pin was among them.
+/// The attempts made for the email are cleared as well.
+///
+/// Used on successful verification so a pin can never be used twice.
+pub async fn consume_verification_pin(pool: &PgPool, email: &str, pin_id: &str) -> Result<bool> {
+    sqlx::query(r#"DELETE FROM verification_attempts WHERE email = $1"#)
+        .bind(email)
+        .execute(pool)
+        .await?;
+
+    let deleted_ids: Vec<String> = sqlx::query_scalar(
+        r#"
+        DELETE FROM verification_pins
+        WHERE email = $1
+        RETURNING id
+    "#,
+    )
+    .bind(email)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(deleted_ids.iter().any(|id| id == pin_id))
 }
 
+/// Creates a new pin for the email, invalidating any pins issued before it.
 pub async fn create_verification_pin(
     pool: &PgPool,
     email: String,
     pin: String,
     expires_at: chrono::DateTime<chrono::Utc>,
 ) -> Result<VerificationPin> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        r#"
+        UPDATE verification_pins
+        SET invalidated_at = NOW()
+        WHERE email = $1 AND invalidated_at IS NULL
+    "#,
+    )
+    .bind(&email)
+    .execute(&mut *tx)
+    .await?;
+
     let verification_pin: VerificationPin = sqlx::query_as(
         r#"INSERT INTO verification_pins
-            (email, pin, expires_at)    
+            (email, pin, expires_at)
             VALUES ($1, $2, $3)
             RETURNING *
         "#,
@@ -30,8 +133,10 @@ pub async fn create_verification_pin(
     .bind(email)
     .bind(pin)
     .bind(expires_at)
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
 
+    tx.commit().await?;
+
     Ok(verification_pin)
 }
@@ -1,16 +1,50 @@
 use std::fmt;
 
-use serde::{Deserialize, de::Visitor};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize, de::Visitor};
 
-use crate::constants::DEFAULT_POSTS_PAGINATION_LIMIT;
+use crate::constants::{DEFAULT_POSTS_PAGINATION_LIMIT, MAX_PAGINATION_LIMIT};
 
 pub struct PaginationQuery {
     pub offset: i64,
     pub limit: i64,
+    /// Set when the client asked for cursor pagination, empty for the first page.
+    pub cursor: Option<String>,
+    /// Counting every matching row is expensive, so the total is only computed on request.
+    pub include_total: bool,
+}
+
+impl PaginationQuery {
+    /// Lists are paginated by offset unless the client sends a `cursor`, in which
+    /// case `offset` is ignored. Returns `None` for a malformed cursor.
+    pub fn page(&self) -> Option<Page> {
+        match self.cursor.as_deref() {
+            None => Some(Page::Offset(self.offset)),
+            Some("") => Some(Page::After(None)),
+            Some(cursor) => Cursor::decode(cursor).map(|cursor| Page::After(Some(cursor))),
+        }
+    }
+
+    /// Like [`PaginationQuery::page`] for lists that are only walked by cursor, the
+    /// first page being the one requested without a cursor. `offset` is ignored.
+    pub fn cursor_page(&self) -> Option<Page> {
+        match self.cursor.as_deref() {
+            None | Some("") => Some(Page::After(None)),
+            Some(cursor) => Cursor::decode(cursor).map(|cursor| Page::After(Some(cursor))),
+        }
+    }
+}
+
+pub enum Page {
+    Offset(i64),
+    /// Items after the cursor, or the first page when there is none.
+    After(Option<Cursor>),
 }
 
 // both offset and limit can be missing or invalid type (string that cannot be parsed to i64)
-// in those cases we use default values
+// in those cases we use default values, limit is capped at `MAX_PAGINATION_LIMIT`.
+// cursor is optional and validated by `PaginationQuery::page`, total is a boolean flag
 
 struct PaginationQueryVisitor;
 
@@ -18,7 +52,7 @@ impl<'de> Visitor<'de> for PaginationQueryVisitor {
     type Value = PaginationQuery;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("a map with offset and limit")
+        formatter.write_str("a map with offset, limit, cursor and total")
     }
 
     fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
@@ -27,6 +61,8 @@ impl<'de> Visitor<'de> for PaginationQueryVisitor {
     {
         let mut offset = None;
         let mut limit = None;
+        let mut cursor = None;
+        let mut include_total = None;
 
         while let Some((key, value)) = map.next_entry::<&str, String>()? {
             match key {
@@ -42,6 +78,18 @@ impl<'de> Visitor<'de> for PaginationQueryVisitor {
                     }
                     limit = Some(value.parse().unwrap_or(default_limit()));
                 }
+                "cursor" => {
+                    if cursor.is_some() {
+                        return Err(serde::de::Error::duplicate_field("cursor"));
+                    }
+                    cursor = Some(value);
+                }
+                "total" => {
+                    if include_total.is_some() {
+                        return Err(serde::de::Error::duplicate_field("total"));
+                    }
+                    include_total = Some(matches!(value.as_str(), "true" | "1"));
+                }
                 _ => (),
             }
         }
@@ -49,15 +97,22 @@ impl<'de> Visitor<'de> for PaginationQueryVisitor {
         let mut offset = offset.unwrap_or(default_offset());
         let mut limit = limit.unwrap_or(default_limit());
 
-        if limit < 0 {
+        if limit < 1 {
             limit = default_limit();
         }
 
+        limit = limit.min(max_limit());
+
         if offset < 0 {
             offset = default_offset();
         }
 
-        Ok(PaginationQuery { offset, limit })
+        Ok(PaginationQuery {
+            offset,
+            limit,
+            cursor,
+            include_total: include_total.unwrap_or(false),
+        })
     }
 }
 
@@ -77,3 +132,174 @@ fn default_offset() -> i64 {
 fn default_limit() -> i64 {
     DEFAULT_POSTS_PAGINATION_LIMIT as i64
 }
+
+fn max_limit() -> i64 {
+    MAX_PAGINATION_LIMIT as i64
+}
+
+/// Position in a list ordered by `(created_at, id)` descending. Handed to clients as
+/// an opaque string.
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub id: String,
+}
+
+impl Cursor {
+    pub fn new(created_at: DateTime<Utc>, id: &str) -> Self {
+        Self {
+            created_at,
+            id: id.to_string(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!(
+            "{}|{}",
+            self.created_at.timestamp_micros(),
+            self.id
+        ))
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let decoded = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+
+        let (created_at, id) = decoded.split_once('|')?;
+        let created_at = DateTime::from_timestamp_micros(created_at.parse().ok()?)?;
+
+        Some(Self::new(created_at, id))
+    }
+}
+
+/// A page of a cursor paginated list.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CursorPage<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+impl<T> CursorPage<T> {
+    /// Builds a page out of up to `limit + 1` items, the extra one only telling
+    /// whether there is a next page.
+    pub fn new(mut items: Vec<T>, limit: i64, cursor_of: impl Fn(&T) -> Cursor) -> Self {
+        let has_more = items.len() as i64 > limit;
+
+        items.truncate(limit as usize);
+
+        let next_cursor = match has_more {
+            true => items.last().map(|item| cursor_of(item).encode()),
+            false => None,
+        };
+
+        Self {
+            items,
+            next_cursor,
+            has_more,
+        }
+    }
+}
+
+/// A page of a list paginated either by offset or by cursor.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub limit: i64,
+    /// Only set for offset pages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<i64>,
+    /// Only set when the client asked for it with `total=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total: Option<i64>,
+    /// Only set for cursor pages that have a next page.
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+}
+
+impl<T> Paginated<T> {
+    /// Builds a page out of up to `limit + 1` items, like [`CursorPage::new`].
+    pub fn new(
+        items: Vec<T>,
+        page: &Page,
+        limit: i64,
+        total: Option<i64>,
+        cursor_of: impl Fn(&T) -> Cursor,
+    ) -> Self {
+        let CursorPage {
+            items,
+            next_cursor,
+            has_more,
+        } = CursorPage::new(items, limit, cursor_of);
+
+        let (offset, next_cursor) = match page {
+            Page::Offset(offset) => (Some(*offset), None),
+            Page::After(_) => (None, next_cursor),
+        };
+
+        Self {
+            items,
+            limit,
+            offset,
+            total,
+            next_cursor,
+            has_more,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(cursor: Option<&str>) -> PaginationQuery {
+        PaginationQuery {
+            offset: 20,
+            limit: 10,
+            cursor: cursor.map(str::to_string),
+            include_total: false,
+        }
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let created_at = DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap();
+        let cursor = Cursor::new(created_at, "pst_a|b");
+
+        let decoded = Cursor::decode(&cursor.encode()).unwrap();
+
+        assert_eq!(decoded.created_at, created_at);
+        assert_eq!(decoded.id, "pst_a|b");
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        let no_separator = URL_SAFE_NO_PAD.encode("1700000000123456");
+        let bad_timestamp = URL_SAFE_NO_PAD.encode("yesterday|pst_1");
+        let not_utf8 = URL_SAFE_NO_PAD.encode([0xff, 0xfe, b'|']);
+
+        for cursor in ["not base64!", &no_separator, &bad_timestamp, &not_utf8] {
+            assert!(Cursor::decode(cursor).is_none(), "{cursor}");
+        }
+    }
+
+    #[test]
+    fn page_is_by_offset_without_a_cursor() {
+        assert!(matches!(query(None).page(), Some(Page::Offset(20))));
+        assert!(matches!(query(Some("")).page(), Some(Page::After(None))));
+        assert!(query(Some("not base64!")).page().is_none());
+    }
+
+    #[test]
+    fn cursor_page_ignores_the_offset() {
+        let cursor = Cursor::new(Utc::now(), "pst_1").encode();
+
+        assert!(matches!(query(None).cursor_page(), Some(Page::After(None))));
+        assert!(matches!(
+            query(Some(&cursor)).cursor_page(),
+            Some(Page::After(Some(c))) if c.id == "pst_1"
+        ));
+        assert!(query(Some("not base64!")).cursor_page().is_none());
+    }
+}